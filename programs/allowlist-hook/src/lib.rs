@@ -0,0 +1,274 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        extension::{transfer_hook::TransferHook, BaseStateWithExtensions, StateWithExtensions},
+        state::Mint as Token2022Mint
+    }, token_interface::{Mint, TokenAccount, TokenInterface}
+};
+use spl_discriminator::SplDiscriminate;
+use spl_tlv_account_resolution::{account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList};
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+declare_id!("B26SkHHz744xsmPNEN5uBhK857Zr1kE4wmWUKaz4M4yn");
+
+#[program]
+pub mod allowlist_hook {
+    use super::*;
+
+    #[instruction(discriminator = 0)]
+    pub fn initialize_allowlist(ctx: Context<InitializeAllowlist>, authority:Pubkey) -> Result<()> {
+
+        // Only the transfer hook authority of the mint can attach an allowlist to it
+        {
+            let mint_info = ctx.accounts.mint.to_account_info();
+            let mint_data = mint_info.try_borrow_data()?;
+            let mint = StateWithExtensions::<Token2022Mint>::unpack(&mint_data)?;
+            let transfer_hook = mint.get_extension::<TransferHook>()?;
+
+            require!(
+                Option::<Pubkey>::from(transfer_hook.program_id) == Some(crate::ID),
+                AllowlistHookError::InvalidTransferHookProgram
+            );
+            require!(
+                Option::<Pubkey>::from(transfer_hook.authority) == Some(ctx.accounts.hook_authority.key()),
+                AllowlistHookError::InvalidTransferHookAuthority
+            );
+        }
+
+        ExtraAccountMetaList::init::<ExecuteInstruction>(
+            &mut ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?,
+            &extra_account_metas()?
+        )?;
+
+        ctx.accounts.allowlist_config.set_inner(
+            AllowlistConfig {
+                authority,
+                bump: ctx.bumps.allowlist_config
+            }
+        );
+        Ok(())
+    }
+
+    #[instruction(discriminator = 1)]
+    pub fn add_to_allowlist(ctx: Context<AddToAllowlist>, wallet:Pubkey) -> Result<()> {
+
+        ctx.accounts.allowlist_entry.set_inner(
+            AllowlistEntry {
+                wallet,
+                bump: ctx.bumps.allowlist_entry
+            }
+        );
+        Ok(())
+    }
+
+    #[instruction(discriminator = 2)]
+    pub fn remove_from_allowlist(_ctx: Context<RemoveFromAllowlist>, _wallet:Pubkey) -> Result<()> {
+        // The entry is closed by the account constraints
+        Ok(())
+    }
+
+    #[instruction(discriminator = ExecuteInstruction::SPL_DISCRIMINATOR_SLICE)]
+    pub fn transfer_hook(ctx: Context<TransferHookExecute>, _amount:u64) -> Result<()> {
+
+        // Both the sender and the receiver have to be on the allowlist of the mint
+        require!(
+            is_allowlisted(&ctx.accounts.source_allowlist_entry),
+            AllowlistHookError::SenderNotAllowlisted
+        );
+        require!(
+            is_allowlisted(&ctx.accounts.destination_allowlist_entry),
+            AllowlistHookError::ReceiverNotAllowlisted
+        );
+        Ok(())
+    }
+}
+
+fn extra_account_metas() -> Result<Vec<ExtraAccountMeta>> {
+
+    // The allowlist entries of the source and destination token account owners, the owner
+    // is stored at offset 32 of a token account
+    Ok(vec![
+        ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal { bytes: b"allowlist".to_vec() },
+                Seed::AccountKey { index: 1 },
+                Seed::AccountData { account_index: 0, data_index: 32, length: 32 }
+            ],
+            false,
+            false
+        )?,
+        ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal { bytes: b"allowlist".to_vec() },
+                Seed::AccountKey { index: 1 },
+                Seed::AccountData { account_index: 2, data_index: 32, length: 32 }
+            ],
+            false,
+            false
+        )?
+    ])
+}
+
+fn is_allowlisted(allowlist_entry: &UncheckedAccount) -> bool {
+    allowlist_entry.owner == &crate::ID && !allowlist_entry.data_is_empty()
+}
+
+#[derive(Accounts)]
+pub struct InitializeAllowlist<'info>{
+
+    #[account(
+        mut
+    )]
+    payer:Signer<'info>,
+
+    hook_authority:Signer<'info>,
+
+    #[account(
+        mint::token_program = token_program,
+    )]
+    mint:InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = ExtraAccountMetaList::size_of(2)?,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    /// CHECK: initialised with the extra account metas of the execute instruction
+    extra_account_meta_list:UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = AllowlistConfig::DISCRIMINATOR.len() + AllowlistConfig::INIT_SPACE,
+        seeds = [b"allowlist-config", mint.key().as_ref()],
+        bump
+    )]
+    allowlist_config:Account<'info, AllowlistConfig>,
+
+    system_program:Program<'info, System>,
+    token_program:Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct AddToAllowlist<'info>{
+
+    #[account(
+        mut
+    )]
+    authority:Signer<'info>,
+
+    mint:InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"allowlist-config", mint.key().as_ref()],
+        bump = allowlist_config.bump,
+        has_one = authority
+    )]
+    allowlist_config:Account<'info, AllowlistConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AllowlistEntry::DISCRIMINATOR.len() + AllowlistEntry::INIT_SPACE,
+        seeds = [b"allowlist", mint.key().as_ref(), wallet.as_ref()],
+        bump
+    )]
+    allowlist_entry:Account<'info, AllowlistEntry>,
+
+    system_program:Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct RemoveFromAllowlist<'info>{
+
+    #[account(
+        mut
+    )]
+    authority:Signer<'info>,
+
+    mint:InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"allowlist-config", mint.key().as_ref()],
+        bump = allowlist_config.bump,
+        has_one = authority
+    )]
+    allowlist_config:Account<'info, AllowlistConfig>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"allowlist", mint.key().as_ref(), wallet.as_ref()],
+        bump = allowlist_entry.bump
+    )]
+    allowlist_entry:Account<'info, AllowlistEntry>,
+}
+
+#[derive(Accounts)]
+pub struct TransferHookExecute<'info>{
+
+    #[account(
+        token::mint = mint,
+    )]
+    source_token:InterfaceAccount<'info, TokenAccount>,
+
+    mint:InterfaceAccount<'info, Mint>,
+
+    #[account(
+        token::mint = mint,
+    )]
+    destination_token:InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: the source token account owner or delegate, checked by the token program
+    owner:UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    /// CHECK: holds the extra account metas read by the token program
+    extra_account_meta_list:UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"allowlist", mint.key().as_ref(), source_token.owner.as_ref()],
+        bump
+    )]
+    /// CHECK: may not exist, in which case the sender is not allowlisted
+    source_allowlist_entry:UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"allowlist", mint.key().as_ref(), destination_token.owner.as_ref()],
+        bump
+    )]
+    /// CHECK: may not exist, in which case the receiver is not allowlisted
+    destination_allowlist_entry:UncheckedAccount<'info>,
+}
+
+#[derive(InitSpace)]
+#[account(discriminator = 1)]
+pub struct AllowlistConfig{
+    pub authority:Pubkey,
+    pub bump:u8
+}
+
+#[derive(InitSpace)]
+#[account(discriminator = 2)]
+pub struct AllowlistEntry{
+    pub wallet:Pubkey,
+    pub bump:u8
+}
+
+#[error_code]
+pub enum AllowlistHookError{
+    #[msg("The mint does not use this program as its transfer hook")]
+    InvalidTransferHookProgram,
+    #[msg("The signer is not the transfer hook authority of the mint")]
+    InvalidTransferHookAuthority,
+    #[msg("The sender is not on the allowlist")]
+    SenderNotAllowlisted,
+    #[msg("The receiver is not on the allowlist")]
+    ReceiverNotAllowlisted
+}
@@ -1,22 +1,89 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*, solana_program::program::invoke, system_program::{
+        create_account, transfer, CreateAccount, Transfer
+    }
+};
+use allowlist_hook::{
+    cpi::{accounts::InitializeAllowlist, initialize_allowlist}, program::AllowlistHook
+};
 use anchor_spl::{
     associated_token::AssociatedToken, token::{
-        burn, mint_to, transfer_checked, Burn, Mint, MintTo, Token, TokenAccount, TransferChecked
+        close_account, transfer_checked, CloseAccount, Mint, Token, TokenAccount, TransferChecked
+    }, token_2022::{
+        self, spl_token_2022::{
+            extension::{confidential_transfer, ExtensionType},
+            solana_zk_sdk::encryption::pod::elgamal::PodElGamalPubkey, state::Mint as Token2022Mint
+        }
+    }, token_interface::{
+        self, burn, freeze_account, initialize_mint2, interest_bearing_mint_initialize,
+        interest_bearing_mint_update_rate, mint_to, non_transferable_mint_initialize, thaw_account,
+        transfer_hook_initialize, Burn, FreezeAccount, InitializeMint2, InterestBearingMintInitialize,
+        InterestBearingMintUpdateRate, MintTo, NonTransferableMintInitialize, ThawAccount, TokenInterface,
+        TransferHookInitialize
     }
 };
 declare_id!("3huwuym1VECMMBjmbGxdy91s9C2QUrpPqn93DQV6dnJz");
 
+/// Swap amount that stands for the entire balance of the account the tokens are taken from, read
+/// when the swap executes.
+pub const SWAP_ALL:u64 = u64::MAX;
+
 #[program]
 pub mod token_wrapper {
     use super::*;
 
     #[instruction(discriminator = 0)]
-    pub fn create_mint(ctx: Context<CreateMint>) -> Result<()> {
+    pub fn create_mint(ctx: Context<CreateMint>, args: CreateMintArgs) -> Result<()> {
+
+        require!(args.unlock_timestamp >= 0, TokenWrapperError::InvalidUnlockTimestamp);
+        require!(args.unwrap_delay >= 0, TokenWrapperError::InvalidUnwrapDelay);
+
+        ctx.accounts.check_creation_policy()?;
+
+        ctx.accounts.charge_creation_fee()?;
+
+        // A source freeze authority can freeze the vault and block every unwrap
+        let source_freezable = ctx.accounts.source_mint.freeze_authority.is_some();
+
+        require!(
+            !source_freezable || args.source_freeze_policy == SourceFreezePolicy::Record,
+            TokenWrapperError::SourceMintFreezable
+        );
+
+        ctx.accounts.create_wrapped_mint(&args)?;
+
+        if let Some(allowlist_authority) = args.allowlist_authority {
+            ctx.accounts.initialize_allowlist(allowlist_authority, ctx.bumps.mint_authority)?;
+        }
         
         ctx.accounts.source_mint_exists.set_inner(
             SourceMint {
+                version: SourceMint::CURRENT_VERSION,
+                source_mint: ctx.accounts.source_mint.key(),
+                vault: ctx.accounts.vault.key(),
+                decimals: ctx.accounts.source_mint.decimals,
+                created_at_slot: Clock::get()?.slot,
                 wrapped_mint: ctx.accounts.wrapped_mint.key(),
-                bump:ctx.bumps.source_mint_exists
+                bump:ctx.bumps.source_mint_exists,
+                unlock_timestamp: args.unlock_timestamp,
+                unwrap_delay: args.unwrap_delay,
+                pending_unwrap: 0,
+                vesting_outstanding: 0,
+                authority: args.authority.unwrap_or(ctx.accounts.payer.key()),
+                pending_authority: None,
+                compliance_authority: args.compliance_authority.unwrap_or_default(),
+                source_freezable,
+                created_at: Clock::get()?.unix_timestamp,
+                vault_authority_bump: ctx.bumps.vault_authority,
+                mint_authority_bump: ctx.bumps.mint_authority,
+                token_program: ctx.accounts.token_program.key(),
+                wrapped_token_program: ctx.accounts.wrapped_token_program.key(),
+                guardian: args.guardian.
+                    or(args.authority).
+                    unwrap_or(ctx.accounts.payer.key()),
+                paused: false,
+                generation: args.generation,
+                reserved: [0; SourceMint::RESERVED_SPACE]
             }
         );
         Ok(())
@@ -25,214 +92,2497 @@ pub mod token_wrapper {
     #[instruction(discriminator = 1)]
     pub fn swap_to_wrapped(ctx: Context<Swap>, amount:u64) -> Result<()> {
 
+        ctx.accounts.source_mint_account.check_not_paused()?;
+
+        let amount = swap_amount(amount, ctx.accounts.buyer_mint_ata.amount);
+
         // Initiate the transfer of tokens from the user to the vault
-        let transfer_accounts = TransferChecked{
-            from: ctx.accounts.buyer_mint_ata.to_account_info(),
-            to:ctx.accounts.vault.to_account_info(),
-            mint:ctx.accounts.source_mint.to_account_info(),
-            authority:ctx.accounts.buyer.to_account_info()
-        };
+        transfer_to_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.buyer_mint_ata,
+            &ctx.accounts.vault,
+            &ctx.accounts.source_mint,
+            &ctx.accounts.buyer,
+            amount
+        )?;
 
-        let transfer_context = 
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(), 
-                transfer_accounts);
+        // Initiate mint
+        mint_wrapped(
+            &ctx.accounts.wrapped_token_program,
+            &ctx.accounts.wrapped_mint,
+            &ctx.accounts.buyer_wrapped_mint_ata,
+            &ctx.accounts.mint_authority,
+            &ctx.accounts.source_mint_account,
+            amount
+        )?;
+
+        ctx.accounts.check_collateral()
+    }
+
+    #[instruction(discriminator = 2)]
+    pub fn swap_to_source(ctx: Context<Swap>, amount:u64) -> Result<()> {
+
+        // Delayed wrappers must go through `request_unwrap` and `claim_unwrap`
+        require!(
+            ctx.accounts.source_mint_account.unwrap_delay == 0,
+            TokenWrapperError::DelayedUnwrapRequired
+        );
+
+        ctx.accounts.source_mint_account.check_not_paused()?;
+        ctx.accounts.source_mint_account.check_unlocked()?;
+
+        let amount = swap_amount(amount, ctx.accounts.buyer_wrapped_mint_ata.amount);
         
-        transfer_checked(transfer_context, amount, ctx.accounts.source_mint.decimals)?;
+        // Initiate the transfer of tokens from the vault to the user
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.buyer_mint_ata,
+            &ctx.accounts.source_mint,
+            &ctx.accounts.vault_authority,
+            &ctx.accounts.source_mint_account,
+            amount
+        )?;
+
+        // Initiate burn
+        burn_wrapped(
+            &ctx.accounts.wrapped_token_program,
+            &ctx.accounts.wrapped_mint,
+            &ctx.accounts.buyer_wrapped_mint_ata,
+            &ctx.accounts.buyer,
+            amount
+        )?;
+
+        ctx.accounts.check_collateral()
+    }
+
+    #[instruction(discriminator = 3)]
+    pub fn request_unwrap(ctx: Context<RequestUnwrap>, _ticket_id:u64, amount:u64) -> Result<()> {
+
+        ctx.accounts.source_mint_account.check_not_paused()?;
+        ctx.accounts.source_mint_account.check_unlocked()?;
+
+        // The wrapped tokens are burned up front, the backing stays in the vault until the claim
+        burn_wrapped(
+            &ctx.accounts.wrapped_token_program,
+            &ctx.accounts.wrapped_mint,
+            &ctx.accounts.owner_wrapped_mint_ata,
+            &ctx.accounts.owner,
+            amount
+        )?;
+
+        let source_mint_account = &mut ctx.accounts.source_mint_account;
+
+        source_mint_account.pending_unwrap = source_mint_account.pending_unwrap.
+            checked_add(amount).ok_or(TokenWrapperError::MathOverflow)?;
+
+        let ready_at = Clock::get()?.unix_timestamp.
+            checked_add(source_mint_account.unwrap_delay).ok_or(TokenWrapperError::MathOverflow)?;
+
+        ctx.accounts.unwrap_ticket.set_inner(
+            UnwrapTicket {
+                owner: ctx.accounts.owner.key(),
+                amount,
+                ready_at,
+                bump: ctx.bumps.unwrap_ticket
+            }
+        );
+        Ok(())
+    }
+
+    #[instruction(discriminator = 4)]
+    pub fn claim_unwrap(ctx: Context<ClaimUnwrap>, _ticket_id:u64) -> Result<()> {
+
+        ctx.accounts.source_mint_account.check_not_paused()?;
+
+        let amount = ctx.accounts.unwrap_ticket.amount;
+
+        require!(
+            Clock::get()?.unix_timestamp >= ctx.accounts.unwrap_ticket.ready_at,
+            TokenWrapperError::UnwrapNotReady
+        );
+
+        let source_mint_account = &mut ctx.accounts.source_mint_account;
+
+        source_mint_account.pending_unwrap = source_mint_account.pending_unwrap.
+            checked_sub(amount).ok_or(TokenWrapperError::MathOverflow)?;
+
+        // Initiate the transfer of tokens from the vault to the owner, the ticket is closed afterwards
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.owner_mint_ata,
+            &ctx.accounts.source_mint,
+            &ctx.accounts.vault_authority,
+            &ctx.accounts.source_mint_account,
+            amount
+        )
+    }
+
+    #[instruction(discriminator = 5)]
+    pub fn create_vesting(
+        ctx: Context<CreateVesting>,
+        _schedule_id:u64,
+        amount:u64,
+        start:i64,
+        cliff:i64,
+        end:i64
+    ) -> Result<()> {
+
+        ctx.accounts.source_mint_account.check_not_paused()?;
+
+        require!(amount > 0, TokenWrapperError::InvalidAmount);
+        require!(
+            start <= cliff && cliff <= end && start < end && end.checked_sub(start).is_some(),
+            TokenWrapperError::InvalidVestingSchedule
+        );
+
+        // Initiate the transfer of tokens from the funder to the vault
+        transfer_to_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.funder_mint_ata,
+            &ctx.accounts.vault,
+            &ctx.accounts.source_mint,
+            &ctx.accounts.funder,
+            amount
+        )?;
+
+        let source_mint_account = &mut ctx.accounts.source_mint_account;
+
+        source_mint_account.vesting_outstanding = source_mint_account.vesting_outstanding.
+            checked_add(amount).ok_or(TokenWrapperError::MathOverflow)?;
+
+        ctx.accounts.vesting_schedule.set_inner(
+            VestingSchedule {
+                beneficiary: ctx.accounts.beneficiary.key(),
+                total_amount: amount,
+                claimed_amount: 0,
+                start,
+                cliff,
+                end,
+                bump: ctx.bumps.vesting_schedule
+            }
+        );
+        Ok(())
+    }
+
+    #[instruction(discriminator = 6)]
+    pub fn claim_vested(ctx: Context<ClaimVested>, _schedule_id:u64) -> Result<()> {
+
+        ctx.accounts.source_mint_account.check_not_paused()?;
+
+        let vesting_schedule = &mut ctx.accounts.vesting_schedule;
+
+        let vested_amount = vesting_schedule.vested_amount(Clock::get()?.unix_timestamp)?;
+
+        let amount = vested_amount.
+            checked_sub(vesting_schedule.claimed_amount).ok_or(TokenWrapperError::MathOverflow)?;
+
+        require!(amount > 0, TokenWrapperError::NothingToClaim);
+
+        vesting_schedule.claimed_amount = vested_amount;
+
+        let source_mint_account = &mut ctx.accounts.source_mint_account;
+
+        source_mint_account.vesting_outstanding = source_mint_account.vesting_outstanding.
+            checked_sub(amount).ok_or(TokenWrapperError::MathOverflow)?;
+
+        // Initiate the transfer of the unlocked tokens from the vault to the beneficiary
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.beneficiary_mint_ata,
+            &ctx.accounts.source_mint,
+            &ctx.accounts.vault_authority,
+            &ctx.accounts.source_mint_account,
+            amount
+        )?;
+
+        // A fully claimed schedule is closed so the beneficiary can be granted a new one
+        if ctx.accounts.vesting_schedule.claimed_amount == ctx.accounts.vesting_schedule.total_amount {
+            ctx.accounts.vesting_schedule.close(ctx.accounts.beneficiary.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    #[instruction(discriminator = 7)]
+    pub fn update_interest_rate(ctx: Context<UpdateInterestRate>, rate:i16) -> Result<()> {
 
         let wrapped_mint_key_bytes = ctx.accounts.wrapped_mint.key().to_bytes();
 
-        let seeds = [b"mint-authority", wrapped_mint_key_bytes.as_ref(), &[ctx.bumps.mint_authority]];
+        let mint_authority_bump = ctx.accounts.source_mint_account.mint_authority_bump;
+
+        let seeds = [b"mint-authority", wrapped_mint_key_bytes.as_ref(), &[mint_authority_bump]];
 
         let signer = &[&seeds[..]];
 
-        // Initiate mint
-        let mint_to_accounts = MintTo{
-            mint:ctx.accounts.wrapped_mint.to_account_info(),
-            to:ctx.accounts.buyer_wrapped_mint_ata.to_account_info(),
-            authority:ctx.accounts.mint_authority.to_account_info()
+        // The mint authority is the rate authority of the interest-bearing extension
+        let update_rate_accounts = InterestBearingMintUpdateRate{
+            token_program_id: ctx.accounts.wrapped_token_program.to_account_info(),
+            mint: ctx.accounts.wrapped_mint.to_account_info(),
+            rate_authority: ctx.accounts.mint_authority.to_account_info()
         };
 
-        let mint_to_context = 
+        let update_rate_context = 
             CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                mint_to_accounts,
+                ctx.accounts.wrapped_token_program.to_account_info(),
+                update_rate_accounts,
                 signer
             );
 
-        mint_to(mint_to_context, amount)
+        interest_bearing_mint_update_rate(update_rate_context, rate)
     }
 
-    #[instruction(discriminator = 2)]
-    pub fn swap_to_source(ctx: Context<Swap>, amount:u64) -> Result<()> {
-        
-        // Initiate the transfer of tokens from the vault to the user
+    #[instruction(discriminator = 8)]
+    pub fn freeze_holder(ctx: Context<ComplianceAction>) -> Result<()> {
 
-        let source_mint_key_bytes = ctx.accounts.source_mint.key().to_bytes();
+        let wrapped_mint_key_bytes = ctx.accounts.wrapped_mint.key().to_bytes();
 
-        let seeds = [b"vault-authority", source_mint_key_bytes.as_ref(), &[ctx.bumps.vault_authority]];
+        let seeds = [b"freeze-authority", wrapped_mint_key_bytes.as_ref(), &[ctx.bumps.freeze_authority]];
 
         let signer = &[&seeds[..]];
 
-        let transfer_accounts = TransferChecked{
-            from: ctx.accounts.vault.to_account_info(),
-            to:ctx.accounts.buyer_mint_ata.to_account_info(),
-            mint:ctx.accounts.source_mint.to_account_info(),
-            authority:ctx.accounts.vault_authority.to_account_info()
+        let freeze_accounts = FreezeAccount{
+            account: ctx.accounts.holder_wrapped_mint_ata.to_account_info(),
+            mint: ctx.accounts.wrapped_mint.to_account_info(),
+            authority: ctx.accounts.freeze_authority.to_account_info()
         };
 
-        let transfer_context = 
+        let freeze_context = 
             CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(), 
-                transfer_accounts,
+                ctx.accounts.wrapped_token_program.to_account_info(),
+                freeze_accounts,
                 signer
             );
-        
-        transfer_checked(transfer_context, amount, ctx.accounts.source_mint.decimals)?;
 
-        // Initiate burn
-        let burn_accounts = Burn{
-            mint:ctx.accounts.wrapped_mint.to_account_info(),
-            from:ctx.accounts.buyer_wrapped_mint_ata.to_account_info(),
-            authority:ctx.accounts.buyer.to_account_info()
+        freeze_account(freeze_context)
+    }
+
+    #[instruction(discriminator = 9)]
+    pub fn thaw_holder(ctx: Context<ComplianceAction>) -> Result<()> {
+
+        let wrapped_mint_key_bytes = ctx.accounts.wrapped_mint.key().to_bytes();
+
+        let seeds = [b"freeze-authority", wrapped_mint_key_bytes.as_ref(), &[ctx.bumps.freeze_authority]];
+
+        let signer = &[&seeds[..]];
+
+        let thaw_accounts = ThawAccount{
+            account: ctx.accounts.holder_wrapped_mint_ata.to_account_info(),
+            mint: ctx.accounts.wrapped_mint.to_account_info(),
+            authority: ctx.accounts.freeze_authority.to_account_info()
         };
 
-        let burn_context = 
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                burn_accounts,
+        let thaw_context = 
+            CpiContext::new_with_signer(
+                ctx.accounts.wrapped_token_program.to_account_info(),
+                thaw_accounts,
+                signer
             );
 
-        burn(burn_context, amount)
+        thaw_account(thaw_context)
     }
-}
 
-#[derive(Accounts)]
-pub struct CreateMint<'info>{
-    #[account(
-        mut
-    )]
-    payer:Signer<'info>,
+    #[instruction(discriminator = 10)]
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        admin:Pubkey,
+        creation_policy:CreationPolicy,
+        creation_fee_lamports:u64,
+        treasury:Pubkey
+    ) -> Result<()> {
 
-    #[account(
-        seeds = [b"mint-authority", wrapped_mint.key().as_ref()],
-        bump
-    )]
-    /// CHECK: just signs
-    mint_authority:UncheckedAccount<'info>,
+        ctx.accounts.config.set_inner(
+            Config {
+                admin,
+                creation_policy,
+                creation_fee_lamports,
+                treasury,
+                bump: ctx.bumps.config
+            }
+        );
+        Ok(())
+    }
 
-    source_mint:Account<'info, Mint>,
+    #[instruction(discriminator = 11)]
+    pub fn set_creation_policy(ctx: Context<UpdateConfig>, creation_policy:CreationPolicy) -> Result<()> {
 
-    #[account(
-        init,
-        payer = payer,
-        mint::decimals = source_mint.decimals,
-        mint::authority = mint_authority,
-    )]
-    wrapped_mint:Account<'info, Mint>,
+        ctx.accounts.config.creation_policy = creation_policy;
+        Ok(())
+    }
 
-    #[account(
-        seeds = [b"vault-authority", source_mint.key().as_ref()],
-        bump
-    )]
-    /// CHECK: just signs
-    vault_authority:UncheckedAccount<'info>,
+    #[instruction(discriminator = 12)]
+    pub fn allow_source_mint(ctx: Context<AllowSourceMint>) -> Result<()> {
 
-    #[account(
-        init,
-        payer = payer,
-        associated_token::mint = source_mint,
-        associated_token::authority = vault_authority,
-    )]
-    vault:Account<'info, TokenAccount>,
+        ctx.accounts.allowed_source_mint.set_inner(
+            AllowedSourceMint {
+                bump: ctx.bumps.allowed_source_mint
+            }
+        );
+        Ok(())
+    }
 
-    #[account(
-        init,
-        payer = payer,
-        space = SourceMint::DISCRIMINATOR.len() + SourceMint::INIT_SPACE,
-        seeds = [b"mint", source_mint.key().as_ref()],
-        bump
-    )]
-    source_mint_exists:Account<'info, SourceMint>,
-    
-    system_program:Program<'info, System>,
-    token_program:Program<'info, Token>,
-    associated_token_program:Program<'info, AssociatedToken>
-}
+    #[instruction(discriminator = 13)]
+    pub fn disallow_source_mint(_ctx: Context<DisallowSourceMint>) -> Result<()> {
+        // The allowlist entry is closed by the account constraints
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct Swap<'info>{
+    #[instruction(discriminator = 14)]
+    pub fn set_creation_fee(ctx: Context<UpdateConfig>, creation_fee_lamports:u64, treasury:Pubkey) -> Result<()> {
 
-    #[account(
-        mut
-    )]
-    buyer:Signer<'info>,
+        let config = &mut ctx.accounts.config;
 
-    #[account(
-        init_if_needed,
-        payer = buyer,
-        associated_token::mint = source_mint,
-        associated_token::authority = buyer,
-        associated_token::token_program = token_program,
-    )]
-    buyer_mint_ata:Account<'info, TokenAccount>,
+        config.creation_fee_lamports = creation_fee_lamports;
+        config.treasury = treasury;
+        Ok(())
+    }
 
-    #[account(
-        init_if_needed,
-        payer = buyer,
-        associated_token::mint = wrapped_mint,
-        associated_token::authority = buyer,
-        associated_token::token_program = token_program,
-    )]
-    buyer_wrapped_mint_ata:Account<'info, TokenAccount>,
+    #[instruction(discriminator = 15)]
+    pub fn transfer_wrapper_authority(ctx: Context<TransferWrapperAuthority>, new_authority:Pubkey) -> Result<()> {
 
-    #[account(
-        seeds = [b"vault-authority", source_mint.key().as_ref()],
-        bump
-    )]
-    /// CHECK: just signs
-    vault_authority:UncheckedAccount<'info>,
+        // The new authority only takes over once it accepts
+        ctx.accounts.source_mint_account.pending_authority = Some(new_authority);
+        Ok(())
+    }
 
-    #[account(
-        mut,
-        associated_token::mint = source_mint,
-        associated_token::authority = vault_authority,
-        associated_token::token_program = token_program,
-    )]
-    vault:Account<'info, TokenAccount>,
+    #[instruction(discriminator = 16)]
+    pub fn accept_wrapper_authority(ctx: Context<AcceptWrapperAuthority>) -> Result<()> {
 
-    #[account(
-        seeds = [b"mint-authority", wrapped_mint.key().as_ref()],
-        bump
-    )]
-    /// CHECK: just signs
-    mint_authority:UncheckedAccount<'info>,
+        let source_mint_account = &mut ctx.accounts.source_mint_account;
 
-    source_mint:Account<'info, Mint>,
+        source_mint_account.authority = ctx.accounts.pending_authority.key();
+        source_mint_account.pending_authority = None;
+        Ok(())
+    }
 
-    #[account(
-        mut,
-        mint::authority = mint_authority,
-    )]
-    wrapped_mint:Account<'info, Mint>,
+    #[instruction(discriminator = 17)]
+    pub fn migrate_wrapper(ctx: Context<MigrateWrapper>) -> Result<()> {
 
-    #[account(
-        seeds = [b"mint", source_mint.key().as_ref()],
-        bump = source_mint_account.bump,
-        has_one = wrapped_mint
-    )]
-    source_mint_account:Account<'info, SourceMint>,
+        let source_mint_account_info = ctx.accounts.source_mint_account.to_account_info();
 
+        // Read the legacy layout, a discriminator followed by the wrapped mint and the bump
+        let (wrapped_mint, bump) = {
+            let data = source_mint_account_info.try_borrow_data()?;
 
-    system_program:Program<'info, System>,
-    token_program:Program<'info, Token>,
-    associated_token_program:Program<'info, AssociatedToken>,
-}
+            require!(
+                data.len() == SourceMint::LEGACY_SPACE && data.starts_with(SourceMint::DISCRIMINATOR),
+                TokenWrapperError::WrapperAlreadyMigrated
+            );
 
+            let offset = SourceMint::DISCRIMINATOR.len();
 
-#[derive(InitSpace)]
-#[account(discriminator = 1)]
-pub struct SourceMint{
-    pub wrapped_mint:Pubkey,
-    pub bump:u8
+            let wrapped_mint = Pubkey::try_from(&data[offset..offset + 32]).
+                map_err(|_| TokenWrapperError::WrapperAlreadyMigrated)?;
+
+            (wrapped_mint, data[offset + 32])
+        };
+
+        require!(bump == ctx.bumps.source_mint_account, TokenWrapperError::WrapperAlreadyMigrated);
+
+        let (_, mint_authority_bump) = Pubkey::find_program_address(
+            &[b"mint-authority", wrapped_mint.as_ref()],
+            &crate::ID
+        );
+
+        // Grow the account to the current layout, the admin covers the extra rent
+        let space = SourceMint::DISCRIMINATOR.len() + SourceMint::INIT_SPACE;
+
+        let rent_shortfall = Rent::get()?.minimum_balance(space).
+            saturating_sub(source_mint_account_info.lamports());
+
+        if rent_shortfall > 0 {
+            let transfer_accounts = Transfer{
+                from: ctx.accounts.admin.to_account_info(),
+                to: source_mint_account_info.clone()
+            };
+
+            let transfer_context = 
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    transfer_accounts
+                );
+
+            transfer(transfer_context, rent_shortfall)?;
+        }
+
+        source_mint_account_info.resize(space)?;
+
+        // Backfill the fields that did not exist in the legacy layout, legacy wrappers are
+        // handed to the admin and record the time of the migration as their creation time.
+        // Legacy wrapped mints were always created under the source token program.
+        let source_mint_account = SourceMint {
+            version: SourceMint::CURRENT_VERSION,
+            source_mint: ctx.accounts.source_mint.key(),
+            vault: ctx.accounts.vault.key(),
+            decimals: ctx.accounts.source_mint.decimals,
+            created_at_slot: Clock::get()?.slot,
+            wrapped_mint,
+            bump,
+            unlock_timestamp: 0,
+            unwrap_delay: 0,
+            pending_unwrap: 0,
+            vesting_outstanding: 0,
+            authority: ctx.accounts.admin.key(),
+            pending_authority: None,
+            compliance_authority: Pubkey::default(),
+            source_freezable: ctx.accounts.source_mint.freeze_authority.is_some(),
+            created_at: Clock::get()?.unix_timestamp,
+            vault_authority_bump: ctx.bumps.vault_authority,
+            mint_authority_bump,
+            token_program: ctx.accounts.token_program.key(),
+            wrapped_token_program: ctx.accounts.token_program.key(),
+            guardian: ctx.accounts.admin.key(),
+            paused: false,
+            generation: 0,
+            reserved: [0; SourceMint::RESERVED_SPACE]
+        };
+
+        let mut data = source_mint_account_info.try_borrow_mut_data()?;
+
+        source_mint_account.try_serialize(&mut &mut data[..])
+    }
+
+    #[instruction(discriminator = 18)]
+    pub fn verify_reserves(ctx: Context<VerifyReserves>) -> Result<ReservesReport> {
+
+        let vault_amount = ctx.accounts.vault.amount;
+        let wrapped_supply = ctx.accounts.wrapped_mint.supply;
+        let required_backing = ctx.accounts.source_mint_account.required_backing(wrapped_supply)?;
+
+        let report = ReservesReport {
+            vault_amount,
+            wrapped_supply,
+            required_backing,
+            fully_backed: vault_amount >= required_backing
+        };
+
+        // Trip the circuit breaker, wraps and unwraps halt until the guardian resumes the wrapper
+        if !report.fully_backed && !ctx.accounts.source_mint_account.paused {
+            ctx.accounts.source_mint_account.paused = true;
+
+            emit!(WrapperPaused {
+                source_mint: ctx.accounts.source_mint.key(),
+                wrapped_mint: ctx.accounts.wrapped_mint.key(),
+                slot: Clock::get()?.slot,
+                vault_amount,
+                required_backing
+            });
+        }
+
+        emit!(ReservesVerified {
+            source_mint: ctx.accounts.source_mint.key(),
+            wrapped_mint: ctx.accounts.wrapped_mint.key(),
+            slot: Clock::get()?.slot,
+            report: report.clone()
+        });
+
+        // Returned to the caller through the return data
+        Ok(report)
+    }
+
+    #[instruction(discriminator = 19)]
+    pub fn sweep_surplus(ctx: Context<SweepSurplus>) -> Result<()> {
+
+        // Only the tokens above the required backing can leave the vault
+        let required_backing = ctx.accounts.source_mint_account.
+            required_backing(ctx.accounts.wrapped_mint.supply)?;
+
+        let surplus = ctx.accounts.vault.amount.saturating_sub(required_backing);
+
+        require!(surplus > 0, TokenWrapperError::NoSurplus);
+
+        transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.vault,
+            &ctx.accounts.recipient,
+            &ctx.accounts.source_mint,
+            &ctx.accounts.vault_authority,
+            &ctx.accounts.source_mint_account,
+            surplus
+        )
+    }
+
+    #[instruction(discriminator = 20)]
+    pub fn recover_foreign_tokens(ctx: Context<RecoverForeignTokens>) -> Result<()> {
+
+        let source_mint_account = &ctx.accounts.source_mint_account;
+
+        let wrapped_mint_key_bytes = source_mint_account.wrapped_mint.to_bytes();
+
+        let vault_authority_seeds = source_mint_account.vault_authority_seeds();
+        let mint_authority_seeds: [&[u8]; 3] =
+            [b"mint-authority", wrapped_mint_key_bytes.as_ref(), &[source_mint_account.mint_authority_bump]];
+
+        // Only the token accounts of the wrapper PDAs can be recovered
+        let owner = ctx.accounts.owner.key();
+
+        let is_owner = |seeds: &[&[u8]]| Pubkey::create_program_address(seeds, &crate::ID).
+            is_ok_and(|address| address == owner);
+
+        let seeds: &[&[u8]] = if is_owner(&vault_authority_seeds) {
+            &vault_authority_seeds
+        } else if is_owner(&mint_authority_seeds) {
+            &mint_authority_seeds
+        } else {
+            return err!(TokenWrapperError::InvalidRecoveryOwner);
+        };
+
+        let signer = &[seeds];
+
+        let amount = ctx.accounts.foreign_token_account.amount;
+
+        require!(amount > 0, TokenWrapperError::InvalidAmount);
+
+        let transfer_accounts = token_interface::TransferChecked{
+            from: ctx.accounts.foreign_token_account.to_account_info(),
+            to: ctx.accounts.destination.to_account_info(),
+            mint: ctx.accounts.foreign_mint.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info()
+        };
+
+        let transfer_context = 
+            CpiContext::new_with_signer(
+                ctx.accounts.foreign_token_program.to_account_info(),
+                transfer_accounts,
+                signer
+            );
+
+        token_interface::transfer_checked(transfer_context, amount, ctx.accounts.foreign_mint.decimals)
+    }
+
+    #[instruction(discriminator = 21)]
+    pub fn resume_wrapper(ctx: Context<ResumeWrapper>) -> Result<()> {
+
+        // The shortfall has to be covered before the wrapper can be resumed
+        let required_backing = ctx.accounts.source_mint_account.
+            required_backing(ctx.accounts.wrapped_mint.supply)?;
+
+        require!(
+            ctx.accounts.vault.amount >= required_backing,
+            TokenWrapperError::UndercollateralizedVault
+        );
+
+        ctx.accounts.source_mint_account.paused = false;
+        Ok(())
+    }
+
+    #[instruction(discriminator = 22)]
+    pub fn set_guardian(ctx: Context<SetGuardian>, guardian:Pubkey) -> Result<()> {
+
+        ctx.accounts.source_mint_account.guardian = guardian;
+        Ok(())
+    }
+
+    #[instruction(discriminator = 23)]
+    pub fn close_wrapper(ctx: Context<CloseWrapper>) -> Result<()> {
+
+        // Nothing may be owed to holders, ticket owners or vesting beneficiaries
+        let required_backing = ctx.accounts.source_mint_account.
+            required_backing(ctx.accounts.wrapped_mint.supply)?;
+
+        require!(
+            required_backing == 0 && ctx.accounts.vault.amount == 0,
+            TokenWrapperError::WrapperNotEmpty
+        );
+
+        let seeds = ctx.accounts.source_mint_account.vault_authority_seeds();
+
+        let signer = &[&seeds[..]];
+
+        // Initiate the closing of the vault, the wrapper account is closed by the account constraints
+        let close_accounts = CloseAccount{
+            account: ctx.accounts.vault.to_account_info(),
+            destination: ctx.accounts.authority.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info()
+        };
+
+        let close_context = 
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                close_accounts,
+                signer
+            );
+
+        close_account(close_context)
+    }
+
+    #[instruction(discriminator = 24)]
+    pub fn set_successor(ctx: Context<SetSuccessor>) -> Result<()> {
+
+        ctx.accounts.successor_link.set_inner(
+            SuccessorLink {
+                successor: ctx.accounts.successor_source_mint_account.key(),
+                bump: ctx.bumps.successor_link
+            }
+        );
+        Ok(())
+    }
+
+    #[instruction(discriminator = 25)]
+    pub fn migrate_holder(ctx: Context<ConvertWrapped>, amount:u64) -> Result<()> {
+
+        // Holders can only move to the successor chosen by the wrapper authority
+        let to_source_mint_account = ctx.accounts.to_source_mint_account.key();
+
+        require!(
+            ctx.accounts.successor_link.as_ref().is_some_and(|link| link.successor == to_source_mint_account),
+            TokenWrapperError::InvalidSuccessor
+        );
+
+        ctx.accounts.convert(amount)
+    }
+
+    #[instruction(discriminator = 26)]
+    pub fn convert(ctx: Context<ConvertWrapped>, amount:u64) -> Result<()> {
+        ctx.accounts.convert(amount)
+    }
+
+    /// Wraps `amounts[i]` with the i-th group of `SwapGroup::LEN` accounts in the remaining
+    /// accounts. Every swap succeeds or the whole batch fails, larger batches need a higher
+    /// compute unit limit from the compute budget program.
+    ///
+    /// A group adds seven accounts that are not shared with other groups. The 1232 byte
+    /// transaction size allows four groups, and an address lookup table raises that to eight
+    /// before the limit of 64 accounts per transaction is reached.
+    #[instruction(discriminator = 27)]
+    pub fn batch_swap_to_wrapped<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchSwap<'info>>,
+        amounts:Vec<u64>
+    ) -> Result<()> {
+
+        for (accounts, amount) in ctx.accounts.swap_groups(ctx.remaining_accounts, &amounts)? {
+            let mut group = SwapGroup::load(accounts, &ctx.accounts.buyer)?;
+
+            group.swap_to_wrapped(&ctx.accounts.buyer, &ctx.accounts.token_program, amount)?;
+        }
+        Ok(())
+    }
+
+    /// Unwraps `amounts[i]` with the i-th group of `SwapGroup::LEN` accounts in the remaining
+    /// accounts, with the same all-or-nothing semantics as `batch_swap_to_wrapped`.
+    #[instruction(discriminator = 28)]
+    pub fn batch_swap_to_source<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchSwap<'info>>,
+        amounts:Vec<u64>
+    ) -> Result<()> {
+
+        for (accounts, amount) in ctx.accounts.swap_groups(ctx.remaining_accounts, &amounts)? {
+            let mut group = SwapGroup::load(accounts, &ctx.accounts.buyer)?;
+
+            group.swap_to_source(&ctx.accounts.buyer, &ctx.accounts.token_program, amount)?;
+        }
+        Ok(())
+    }
+}
+
+/// Resolves `SWAP_ALL` to the balance the swap takes its tokens from.
+fn swap_amount(amount:u64, balance:u64) -> u64{
+    if amount == SWAP_ALL {
+        balance
+    } else {
+        amount
+    }
+}
+
+fn transfer_to_vault<'info>(
+    token_program: &Program<'info, Token>,
+    from: &Account<'info, TokenAccount>,
+    vault: &Account<'info, TokenAccount>,
+    source_mint: &Account<'info, Mint>,
+    authority: &Signer<'info>,
+    amount: u64
+) -> Result<()> {
+
+    let transfer_accounts = TransferChecked{
+        from: from.to_account_info(),
+        to: vault.to_account_info(),
+        mint: source_mint.to_account_info(),
+        authority: authority.to_account_info()
+    };
+
+    let transfer_context = 
+        CpiContext::new(
+            token_program.to_account_info(), 
+            transfer_accounts);
+    
+    transfer_checked(transfer_context, amount, source_mint.decimals)
+}
+
+fn transfer_from_vault<'info>(
+    token_program: &Program<'info, Token>,
+    vault: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    source_mint: &Account<'info, Mint>,
+    vault_authority: &UncheckedAccount<'info>,
+    source_mint_account: &SourceMint,
+    amount: u64
+) -> Result<()> {
+
+    require!(!vault.is_frozen(), TokenWrapperError::VaultFrozen);
+
+    let seeds = source_mint_account.vault_authority_seeds();
+
+    let signer = &[&seeds[..]];
+
+    let transfer_accounts = TransferChecked{
+        from: vault.to_account_info(),
+        to: to.to_account_info(),
+        mint: source_mint.to_account_info(),
+        authority: vault_authority.to_account_info()
+    };
+
+    let transfer_context = 
+        CpiContext::new_with_signer(
+            token_program.to_account_info(), 
+            transfer_accounts,
+            signer
+        );
+    
+    transfer_checked(transfer_context, amount, source_mint.decimals)
+}
+
+fn check_collateral<'info>(
+    vault: &mut Account<'info, TokenAccount>,
+    wrapped_mint: &mut InterfaceAccount<'info, token_interface::Mint>,
+    source_mint_account: &SourceMint
+) -> Result<()> {
+
+    // Read the balances left by the token program rather than trusting the amounts
+    vault.reload()?;
+    wrapped_mint.reload()?;
+
+    let required_backing = source_mint_account.required_backing(wrapped_mint.supply)?;
+
+    require!(
+        vault.amount >= required_backing,
+        TokenWrapperError::UndercollateralizedVault
+    );
+    Ok(())
+}
+
+fn mint_wrapped<'info>(
+    wrapped_token_program: &Interface<'info, TokenInterface>,
+    wrapped_mint: &InterfaceAccount<'info, token_interface::Mint>,
+    to: &InterfaceAccount<'info, token_interface::TokenAccount>,
+    mint_authority: &UncheckedAccount<'info>,
+    source_mint_account: &SourceMint,
+    amount: u64
+) -> Result<()> {
+
+    let wrapped_mint_key_bytes = wrapped_mint.key().to_bytes();
+
+    let mint_authority_bump = source_mint_account.mint_authority_bump;
+
+    let seeds = [b"mint-authority", wrapped_mint_key_bytes.as_ref(), &[mint_authority_bump]];
+
+    let signer = &[&seeds[..]];
+
+    let mint_to_accounts = MintTo{
+        mint: wrapped_mint.to_account_info(),
+        to: to.to_account_info(),
+        authority: mint_authority.to_account_info()
+    };
+
+    let mint_to_context = 
+        CpiContext::new_with_signer(
+            wrapped_token_program.to_account_info(),
+            mint_to_accounts,
+            signer
+        );
+
+    mint_to(mint_to_context, amount)
+}
+
+fn burn_wrapped<'info>(
+    wrapped_token_program: &Interface<'info, TokenInterface>,
+    wrapped_mint: &InterfaceAccount<'info, token_interface::Mint>,
+    from: &InterfaceAccount<'info, token_interface::TokenAccount>,
+    authority: &Signer<'info>,
+    amount: u64
+) -> Result<()> {
+
+    let burn_accounts = Burn{
+        mint: wrapped_mint.to_account_info(),
+        from: from.to_account_info(),
+        authority: authority.to_account_info()
+    };
+
+    let burn_context = 
+        CpiContext::new(
+            wrapped_token_program.to_account_info(),
+            burn_accounts,
+        );
+
+    burn(burn_context, amount)
+}
+
+#[derive(Accounts)]
+#[instruction(args: CreateMintArgs)]
+pub struct CreateMint<'info>{
+    #[account(
+        mut
+    )]
+    payer:Signer<'info>,
+
+    #[account(
+        seeds = [b"mint-authority", wrapped_mint.key().as_ref()],
+        bump
+    )]
+    /// CHECK: just signs
+    mint_authority:UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"freeze-authority", wrapped_mint.key().as_ref()],
+        bump
+    )]
+    /// CHECK: just signs
+    freeze_authority:UncheckedAccount<'info>,
+
+    source_mint:Account<'info, Mint>,
+
+    #[account(
+        mut
+    )]
+    /// Created in `create_wrapped_mint` so the extensions can be initialised before the mint
+    wrapped_mint:Signer<'info>,
+
+    #[account(
+        seeds = [b"vault-authority", source_mint.key().as_ref(), args.generation_seed()],
+        bump
+    )]
+    /// CHECK: just signs
+    vault_authority:UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = source_mint,
+        associated_token::authority = vault_authority,
+    )]
+    vault:Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = SourceMint::DISCRIMINATOR.len() + SourceMint::INIT_SPACE,
+        seeds = [b"mint", source_mint.key().as_ref(), args.generation_seed()],
+        bump
+    )]
+    source_mint_exists:Account<'info, SourceMint>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    config:Account<'info, Config>,
+
+    #[account(
+        seeds = [b"allowed-source", source_mint.key().as_ref()],
+        bump = allowed_source_mint.bump
+    )]
+    allowed_source_mint:Option<Account<'info, AllowedSourceMint>>,
+
+    #[account(
+        mut,
+        address = config.treasury
+    )]
+    /// CHECK: receives the creation fee
+    treasury:UncheckedAccount<'info>,
+    
+    system_program:Program<'info, System>,
+    token_program:Program<'info, Token>,
+    wrapped_token_program:Interface<'info, TokenInterface>,
+    associated_token_program:Program<'info, AssociatedToken>,
+
+    // Only needed when the wrapped mint is created with the allowlist transfer hook
+    allowlist_hook_program:Option<Program<'info, AllowlistHook>>,
+
+    #[account(
+        mut
+    )]
+    /// CHECK: initialised and validated by the allowlist hook program
+    extra_account_meta_list:Option<UncheckedAccount<'info>>,
+
+    #[account(
+        mut
+    )]
+    /// CHECK: initialised and validated by the allowlist hook program
+    allowlist_config:Option<UncheckedAccount<'info>>
+}
+
+impl<'info> CreateMint<'info>{
+    fn check_creation_policy(&self) -> Result<()>{
+
+        // The admin can always create wrappers, so a popular source cannot be squatted before
+        // it is allowlisted
+        if self.payer.key() == self.config.admin {
+            return Ok(());
+        }
+
+        match self.config.creation_policy {
+            CreationPolicy::Open => Ok(()),
+            CreationPolicy::AdminOnly => err!(TokenWrapperError::Unauthorized),
+            CreationPolicy::Allowlisted => {
+                require!(
+                    self.allowed_source_mint.is_some(),
+                    TokenWrapperError::SourceMintNotAllowlisted
+                );
+                Ok(())
+            }
+        }
+    }
+
+    fn charge_creation_fee(&self) -> Result<()>{
+
+        // Wrappers created by the admin are not charged
+        if self.config.creation_fee_lamports == 0 || self.payer.key() == self.config.admin {
+            return Ok(());
+        }
+
+        let transfer_accounts = Transfer{
+            from: self.payer.to_account_info(),
+            to: self.treasury.to_account_info()
+        };
+
+        let transfer_context = 
+            CpiContext::new(
+                self.system_program.to_account_info(),
+                transfer_accounts
+            );
+
+        transfer(transfer_context, self.config.creation_fee_lamports)
+    }
+
+    fn create_wrapped_mint(&self, args:&CreateMintArgs) -> Result<()>{
+
+        let extensions = args.wrapped_mint_extensions();
+
+        if !extensions.is_empty() {
+            require_keys_eq!(
+                self.wrapped_token_program.key(),
+                token_2022::ID,
+                TokenWrapperError::ExtensionsRequireToken2022
+            );
+        }
+
+        // Allocate the wrapped mint with room for its extensions
+        let space = ExtensionType::try_calculate_account_len::<Token2022Mint>(&extensions)?;
+
+        let create_account_accounts = CreateAccount{
+            from: self.payer.to_account_info(),
+            to: self.wrapped_mint.to_account_info()
+        };
+
+        let create_account_context = 
+            CpiContext::new(
+                self.system_program.to_account_info(),
+                create_account_accounts
+            );
+
+        create_account(
+            create_account_context,
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            &self.wrapped_token_program.key()
+        )?;
+
+        // The extensions have to be initialised before the mint itself
+        if let Some(rate) = args.interest_rate {
+            let interest_bearing_accounts = InterestBearingMintInitialize{
+                token_program_id: self.wrapped_token_program.to_account_info(),
+                mint: self.wrapped_mint.to_account_info()
+            };
+
+            interest_bearing_mint_initialize(
+                CpiContext::new(
+                    self.wrapped_token_program.to_account_info(),
+                    interest_bearing_accounts
+                ),
+                Some(self.mint_authority.key()),
+                rate
+            )?;
+        }
+
+        if args.allowlist_authority.is_some() {
+            let transfer_hook_accounts = TransferHookInitialize{
+                token_program_id: self.wrapped_token_program.to_account_info(),
+                mint: self.wrapped_mint.to_account_info()
+            };
+
+            transfer_hook_initialize(
+                CpiContext::new(
+                    self.wrapped_token_program.to_account_info(),
+                    transfer_hook_accounts
+                ),
+                Some(self.mint_authority.key()),
+                Some(allowlist_hook::ID)
+            )?;
+        }
+
+        if let Some(confidential_transfer_args) = &args.confidential_transfer {
+            let initialize_confidential_transfer_instruction = confidential_transfer::instruction::initialize_mint(
+                &self.wrapped_token_program.key(),
+                &self.wrapped_mint.key(),
+                confidential_transfer_args.authority,
+                confidential_transfer_args.auto_approve_new_accounts,
+                confidential_transfer_args.auditor_elgamal_pubkey.map(PodElGamalPubkey::from)
+            )?;
+
+            invoke(
+                &initialize_confidential_transfer_instruction,
+                &[
+                    self.wrapped_token_program.to_account_info(),
+                    self.wrapped_mint.to_account_info()
+                ]
+            )?;
+        }
+
+        if args.non_transferable {
+            let non_transferable_accounts = NonTransferableMintInitialize{
+                token_program_id: self.wrapped_token_program.to_account_info(),
+                mint: self.wrapped_mint.to_account_info()
+            };
+
+            non_transferable_mint_initialize(
+                CpiContext::new(
+                    self.wrapped_token_program.to_account_info(),
+                    non_transferable_accounts
+                )
+            )?;
+        }
+
+        let initialize_mint_accounts = InitializeMint2{
+            mint: self.wrapped_mint.to_account_info()
+        };
+
+        let initialize_mint_context = 
+            CpiContext::new(
+                self.wrapped_token_program.to_account_info(),
+                initialize_mint_accounts
+            );
+
+        // Wrappers with a compliance role get a freeze authority PDA controlled by the wrapper
+        let freeze_authority = args.compliance_authority.map(|_| self.freeze_authority.key());
+
+        initialize_mint2(
+            initialize_mint_context,
+            self.source_mint.decimals,
+            &self.mint_authority.key(),
+            freeze_authority.as_ref()
+        )
+    }
+
+    fn initialize_allowlist(&self, allowlist_authority:Pubkey, mint_authority_bump:u8) -> Result<()>{
+
+        let (Some(allowlist_hook_program), Some(extra_account_meta_list), Some(allowlist_config)) = (
+            self.allowlist_hook_program.as_ref(),
+            self.extra_account_meta_list.as_ref(),
+            self.allowlist_config.as_ref()
+        ) else {
+            return err!(TokenWrapperError::MissingAllowlistAccounts);
+        };
+
+        let wrapped_mint_key_bytes = self.wrapped_mint.key().to_bytes();
+
+        let seeds = [b"mint-authority", wrapped_mint_key_bytes.as_ref(), &[mint_authority_bump]];
+
+        let signer = &[&seeds[..]];
+
+        // The mint authority is the transfer hook authority, so it vouches for the allowlist
+        let initialize_allowlist_accounts = InitializeAllowlist{
+            payer: self.payer.to_account_info(),
+            hook_authority: self.mint_authority.to_account_info(),
+            mint: self.wrapped_mint.to_account_info(),
+            extra_account_meta_list: extra_account_meta_list.to_account_info(),
+            allowlist_config: allowlist_config.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            token_program: self.wrapped_token_program.to_account_info()
+        };
+
+        let initialize_allowlist_context = 
+            CpiContext::new_with_signer(
+                allowlist_hook_program.to_account_info(),
+                initialize_allowlist_accounts,
+                signer
+            );
+
+        initialize_allowlist(initialize_allowlist_context, allowlist_authority)
+    }
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info>{
+
+    #[account(
+        mut
+    )]
+    buyer:Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = source_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program,
+    )]
+    buyer_mint_ata:Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = wrapped_mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = wrapped_token_program,
+    )]
+    buyer_wrapped_mint_ata:InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        seeds = [b"vault-authority", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.vault_authority_bump
+    )]
+    /// CHECK: just signs
+    vault_authority:UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = source_mint,
+        associated_token::authority = vault_authority,
+        associated_token::token_program = token_program,
+    )]
+    vault:Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"mint-authority", wrapped_mint.key().as_ref()],
+        bump = source_mint_account.mint_authority_bump
+    )]
+    /// CHECK: just signs
+    mint_authority:UncheckedAccount<'info>,
+
+    source_mint:Account<'info, Mint>,
+
+    #[account(
+        mut,
+        mint::authority = mint_authority,
+        mint::token_program = wrapped_token_program,
+    )]
+    wrapped_mint:InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        seeds = [b"mint", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.bump,
+        has_one = wrapped_mint
+    )]
+    source_mint_account:Account<'info, SourceMint>,
+
+
+    system_program:Program<'info, System>,
+    token_program:Program<'info, Token>,
+    wrapped_token_program:Interface<'info, TokenInterface>,
+    associated_token_program:Program<'info, AssociatedToken>,
+}
+
+impl<'info> Swap<'info>{
+    fn check_collateral(&mut self) -> Result<()>{
+        check_collateral(&mut self.vault, &mut self.wrapped_mint, &self.source_mint_account)
+    }
+}
+
+
+#[derive(Accounts)]
+#[instruction(ticket_id: u64)]
+pub struct RequestUnwrap<'info>{
+
+    #[account(
+        mut
+    )]
+    owner:Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = wrapped_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = wrapped_token_program,
+    )]
+    owner_wrapped_mint_ata:InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    source_mint:Account<'info, Mint>,
+
+    #[account(
+        mut,
+        mint::token_program = wrapped_token_program,
+    )]
+    wrapped_mint:InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"mint", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.bump,
+        has_one = wrapped_mint
+    )]
+    source_mint_account:Account<'info, SourceMint>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = UnwrapTicket::DISCRIMINATOR.len() + UnwrapTicket::INIT_SPACE,
+        seeds = [b"unwrap-ticket", source_mint_account.key().as_ref(), owner.key().as_ref(), &ticket_id.to_le_bytes()],
+        bump
+    )]
+    unwrap_ticket:Account<'info, UnwrapTicket>,
+
+    system_program:Program<'info, System>,
+    wrapped_token_program:Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(ticket_id: u64)]
+pub struct ClaimUnwrap<'info>{
+
+    #[account(
+        mut
+    )]
+    owner:Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = source_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    owner_mint_ata:Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"vault-authority", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.vault_authority_bump
+    )]
+    /// CHECK: just signs
+    vault_authority:UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = source_mint,
+        associated_token::authority = vault_authority,
+        associated_token::token_program = token_program,
+    )]
+    vault:Account<'info, TokenAccount>,
+
+    source_mint:Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"mint", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.bump,
+    )]
+    source_mint_account:Account<'info, SourceMint>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"unwrap-ticket", source_mint_account.key().as_ref(), owner.key().as_ref(), &ticket_id.to_le_bytes()],
+        bump = unwrap_ticket.bump,
+        has_one = owner
+    )]
+    unwrap_ticket:Account<'info, UnwrapTicket>,
+
+    system_program:Program<'info, System>,
+    token_program:Program<'info, Token>,
+    associated_token_program:Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+#[instruction(schedule_id: u64)]
+pub struct CreateVesting<'info>{
+
+    #[account(
+        mut
+    )]
+    funder:Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = source_mint,
+        associated_token::authority = funder,
+        associated_token::token_program = token_program,
+    )]
+    funder_mint_ata:Account<'info, TokenAccount>,
+
+    /// CHECK: only recorded as the beneficiary of the schedule
+    beneficiary:UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"vault-authority", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.vault_authority_bump
+    )]
+    /// CHECK: just signs
+    vault_authority:UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = source_mint,
+        associated_token::authority = vault_authority,
+        associated_token::token_program = token_program,
+    )]
+    vault:Account<'info, TokenAccount>,
+
+    source_mint:Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"mint", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.bump,
+    )]
+    source_mint_account:Account<'info, SourceMint>,
+
+    #[account(
+        init,
+        payer = funder,
+        space = VestingSchedule::DISCRIMINATOR.len() + VestingSchedule::INIT_SPACE,
+        seeds = [b"vesting", source_mint_account.key().as_ref(), beneficiary.key().as_ref(), &schedule_id.to_le_bytes()],
+        bump
+    )]
+    vesting_schedule:Account<'info, VestingSchedule>,
+
+    system_program:Program<'info, System>,
+    token_program:Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(schedule_id: u64)]
+pub struct ClaimVested<'info>{
+
+    #[account(
+        mut
+    )]
+    beneficiary:Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        associated_token::mint = source_mint,
+        associated_token::authority = beneficiary,
+        associated_token::token_program = token_program,
+    )]
+    beneficiary_mint_ata:Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"vault-authority", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.vault_authority_bump
+    )]
+    /// CHECK: just signs
+    vault_authority:UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = source_mint,
+        associated_token::authority = vault_authority,
+        associated_token::token_program = token_program,
+    )]
+    vault:Account<'info, TokenAccount>,
+
+    source_mint:Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"mint", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.bump,
+    )]
+    source_mint_account:Account<'info, SourceMint>,
+
+    #[account(
+        mut,
+        seeds = [b"vesting", source_mint_account.key().as_ref(), beneficiary.key().as_ref(), &schedule_id.to_le_bytes()],
+        bump = vesting_schedule.bump,
+        has_one = beneficiary
+    )]
+    vesting_schedule:Account<'info, VestingSchedule>,
+
+    system_program:Program<'info, System>,
+    token_program:Program<'info, Token>,
+    associated_token_program:Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateInterestRate<'info>{
+
+    authority:Signer<'info>,
+
+    #[account(
+        seeds = [b"mint-authority", wrapped_mint.key().as_ref()],
+        bump = source_mint_account.mint_authority_bump
+    )]
+    /// CHECK: just signs
+    mint_authority:UncheckedAccount<'info>,
+
+    source_mint:Account<'info, Mint>,
+
+    #[account(
+        mut,
+        mint::authority = mint_authority,
+        mint::token_program = wrapped_token_program,
+    )]
+    wrapped_mint:InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        seeds = [b"mint", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.bump,
+        has_one = wrapped_mint,
+        has_one = authority
+    )]
+    source_mint_account:Account<'info, SourceMint>,
+
+    wrapped_token_program:Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ComplianceAction<'info>{
+
+    compliance_authority:Signer<'info>,
+
+    #[account(
+        seeds = [b"freeze-authority", wrapped_mint.key().as_ref()],
+        bump
+    )]
+    /// CHECK: just signs
+    freeze_authority:UncheckedAccount<'info>,
+
+    source_mint:Account<'info, Mint>,
+
+    #[account(
+        mint::freeze_authority = freeze_authority,
+        mint::token_program = wrapped_token_program,
+    )]
+    wrapped_mint:InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        seeds = [b"mint", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.bump,
+        has_one = wrapped_mint,
+        has_one = compliance_authority
+    )]
+    source_mint_account:Account<'info, SourceMint>,
+
+    #[account(
+        mut,
+        token::mint = wrapped_mint,
+        token::token_program = wrapped_token_program,
+    )]
+    holder_wrapped_mint_ata:InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    wrapped_token_program:Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info>{
+
+    #[account(
+        mut
+    )]
+    upgrade_authority:Signer<'info>,
+
+    #[account(
+        init,
+        payer = upgrade_authority,
+        space = Config::DISCRIMINATOR.len() + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    config:Account<'info, Config>,
+
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key())
+    )]
+    program:Program<'info, crate::program::TokenWrapper>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(upgrade_authority.key())
+            @ TokenWrapperError::Unauthorized
+    )]
+    program_data:Account<'info, ProgramData>,
+
+    system_program:Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info>{
+
+    admin:Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    config:Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct AllowSourceMint<'info>{
+
+    #[account(
+        mut
+    )]
+    admin:Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    config:Account<'info, Config>,
+
+    source_mint:Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = AllowedSourceMint::DISCRIMINATOR.len() + AllowedSourceMint::INIT_SPACE,
+        seeds = [b"allowed-source", source_mint.key().as_ref()],
+        bump
+    )]
+    allowed_source_mint:Account<'info, AllowedSourceMint>,
+
+    system_program:Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DisallowSourceMint<'info>{
+
+    #[account(
+        mut
+    )]
+    admin:Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    config:Account<'info, Config>,
+
+    source_mint:Account<'info, Mint>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"allowed-source", source_mint.key().as_ref()],
+        bump = allowed_source_mint.bump
+    )]
+    allowed_source_mint:Account<'info, AllowedSourceMint>,
+}
+
+#[derive(Accounts)]
+pub struct TransferWrapperAuthority<'info>{
+
+    authority:Signer<'info>,
+
+    source_mint:Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"mint", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.bump,
+        has_one = authority
+    )]
+    source_mint_account:Account<'info, SourceMint>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptWrapperAuthority<'info>{
+
+    pending_authority:Signer<'info>,
+
+    source_mint:Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"mint", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.bump,
+        constraint = source_mint_account.pending_authority == Some(pending_authority.key())
+            @ TokenWrapperError::Unauthorized
+    )]
+    source_mint_account:Account<'info, SourceMint>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateWrapper<'info>{
+
+    #[account(
+        mut
+    )]
+    admin:Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    config:Account<'info, Config>,
+
+    source_mint:Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"vault-authority", source_mint.key().as_ref()],
+        bump
+    )]
+    /// CHECK: just signs
+    vault_authority:UncheckedAccount<'info>,
+
+    #[account(
+        associated_token::mint = source_mint,
+        associated_token::authority = vault_authority,
+        associated_token::token_program = token_program,
+    )]
+    vault:Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"mint", source_mint.key().as_ref()],
+        bump
+    )]
+    /// CHECK: still in the legacy layout, which `Account` cannot deserialize
+    source_mint_account:UncheckedAccount<'info>,
+
+    system_program:Program<'info, System>,
+    token_program:Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyReserves<'info>{
+
+    source_mint:Account<'info, Mint>,
+
+    wrapped_mint:InterfaceAccount<'info, token_interface::Mint>,
+
+    vault:Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"mint", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.bump,
+        has_one = wrapped_mint,
+        has_one = vault
+    )]
+    source_mint_account:Account<'info, SourceMint>,
+}
+
+#[derive(Accounts)]
+pub struct SweepSurplus<'info>{
+
+    authority:Signer<'info>,
+
+    #[account(
+        seeds = [b"vault-authority", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.vault_authority_bump
+    )]
+    /// CHECK: just signs
+    vault_authority:UncheckedAccount<'info>,
+
+    #[account(
+        mut
+    )]
+    vault:Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = source_mint,
+    )]
+    recipient:Account<'info, TokenAccount>,
+
+    source_mint:Account<'info, Mint>,
+
+    wrapped_mint:InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        seeds = [b"mint", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.bump,
+        has_one = wrapped_mint,
+        has_one = vault,
+        has_one = authority
+    )]
+    source_mint_account:Account<'info, SourceMint>,
+
+    token_program:Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RecoverForeignTokens<'info>{
+
+    admin:Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    config:Account<'info, Config>,
+
+    source_mint:Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"mint", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.bump
+    )]
+    source_mint_account:Account<'info, SourceMint>,
+
+    /// CHECK: either the vault authority or the mint authority of the wrapper, checked in the handler
+    owner:UncheckedAccount<'info>,
+
+    #[account(
+        constraint = foreign_mint.key() != source_mint.key() @ TokenWrapperError::SourceMintNotRecoverable,
+        mint::token_program = foreign_token_program,
+    )]
+    foreign_mint:InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = foreign_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = foreign_token_program,
+    )]
+    foreign_token_account:InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = foreign_mint,
+        token::token_program = foreign_token_program,
+    )]
+    destination:InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    foreign_token_program:Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ResumeWrapper<'info>{
+
+    guardian:Signer<'info>,
+
+    source_mint:Account<'info, Mint>,
+
+    wrapped_mint:InterfaceAccount<'info, token_interface::Mint>,
+
+    vault:Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"mint", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.bump,
+        has_one = wrapped_mint,
+        has_one = vault,
+        has_one = guardian,
+        constraint = source_mint_account.paused @ TokenWrapperError::WrapperNotPaused
+    )]
+    source_mint_account:Account<'info, SourceMint>,
+}
+
+#[derive(Accounts)]
+pub struct SetGuardian<'info>{
+
+    authority:Signer<'info>,
+
+    source_mint:Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"mint", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.bump,
+        has_one = authority
+    )]
+    source_mint_account:Account<'info, SourceMint>,
+}
+
+#[derive(Accounts)]
+pub struct CloseWrapper<'info>{
+
+    #[account(
+        mut
+    )]
+    authority:Signer<'info>,
+
+    #[account(
+        seeds = [b"vault-authority", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.vault_authority_bump
+    )]
+    /// CHECK: just signs
+    vault_authority:UncheckedAccount<'info>,
+
+    #[account(
+        mut
+    )]
+    vault:Account<'info, TokenAccount>,
+
+    source_mint:Account<'info, Mint>,
+
+    wrapped_mint:InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"mint", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.bump,
+        has_one = wrapped_mint,
+        has_one = vault,
+        has_one = authority
+    )]
+    source_mint_account:Account<'info, SourceMint>,
+
+    token_program:Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetSuccessor<'info>{
+
+    #[account(
+        mut
+    )]
+    authority:Signer<'info>,
+
+    source_mint:Account<'info, Mint>,
+
+    #[account(
+        seeds = [b"mint", source_mint.key().as_ref(), source_mint_account.generation_seed()],
+        bump = source_mint_account.bump,
+        has_one = authority
+    )]
+    source_mint_account:Account<'info, SourceMint>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = SuccessorLink::DISCRIMINATOR.len() + SuccessorLink::INIT_SPACE,
+        seeds = [b"successor", source_mint_account.key().as_ref()],
+        bump
+    )]
+    successor_link:Account<'info, SuccessorLink>,
+
+    #[account(
+        seeds = [b"mint", source_mint.key().as_ref(), successor_source_mint_account.generation_seed()],
+        bump = successor_source_mint_account.bump,
+        constraint = successor_source_mint_account.key() != source_mint_account.key()
+            @ TokenWrapperError::InvalidSuccessor
+    )]
+    successor_source_mint_account:Account<'info, SourceMint>,
+
+    system_program:Program<'info, System>,
+}
+
+// The mints, wrapped token accounts and wrapper accounts are boxed to keep the account
+// validation within the stack frame limit
+#[derive(Accounts)]
+pub struct ConvertWrapped<'info>{
+
+    #[account(
+        mut
+    )]
+    holder:Signer<'info>,
+
+    source_mint:Account<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = from_wrapped_mint,
+        token::token_program = from_wrapped_token_program,
+    )]
+    from_holder_wrapped_mint_ata:Box<InterfaceAccount<'info, token_interface::TokenAccount>>,
+
+    #[account(
+        seeds = [b"vault-authority", source_mint.key().as_ref(), from_source_mint_account.generation_seed()],
+        bump = from_source_mint_account.vault_authority_bump
+    )]
+    /// CHECK: just signs
+    from_vault_authority:UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        address = from_source_mint_account.vault
+    )]
+    from_vault:Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = from_source_mint_account.wrapped_mint,
+        mint::token_program = from_wrapped_token_program,
+    )]
+    from_wrapped_mint:Box<InterfaceAccount<'info, token_interface::Mint>>,
+
+    #[account(
+        seeds = [b"mint", source_mint.key().as_ref(), from_source_mint_account.generation_seed()],
+        bump = from_source_mint_account.bump
+    )]
+    from_source_mint_account:Box<Account<'info, SourceMint>>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        associated_token::mint = to_wrapped_mint,
+        associated_token::authority = holder,
+        associated_token::token_program = to_wrapped_token_program,
+    )]
+    to_holder_wrapped_mint_ata:Box<InterfaceAccount<'info, token_interface::TokenAccount>>,
+
+    #[account(
+        mut,
+        address = to_source_mint_account.vault
+    )]
+    to_vault:Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"mint-authority", to_wrapped_mint.key().as_ref()],
+        bump = to_source_mint_account.mint_authority_bump
+    )]
+    /// CHECK: just signs
+    to_mint_authority:UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        address = to_source_mint_account.wrapped_mint,
+        mint::authority = to_mint_authority,
+        mint::token_program = to_wrapped_token_program,
+    )]
+    to_wrapped_mint:Box<InterfaceAccount<'info, token_interface::Mint>>,
+
+    #[account(
+        seeds = [b"mint", source_mint.key().as_ref(), to_source_mint_account.generation_seed()],
+        bump = to_source_mint_account.bump
+    )]
+    to_source_mint_account:Box<Account<'info, SourceMint>>,
+
+    system_program:Program<'info, System>,
+    token_program:Program<'info, Token>,
+    from_wrapped_token_program:Interface<'info, TokenInterface>,
+    to_wrapped_token_program:Interface<'info, TokenInterface>,
+    associated_token_program:Program<'info, AssociatedToken>,
+
+    #[account(
+        seeds = [b"successor", from_source_mint_account.key().as_ref()],
+        bump = successor_link.bump
+    )]
+    /// Only read by `migrate_holder`
+    successor_link:Option<Account<'info, SuccessorLink>>,
+}
+
+impl<'info> ConvertWrapped<'info>{
+    /// Burns `amount` wrapped tokens of the from wrapper, moves their backing to the vault of the
+    /// to wrapper and mints the same amount of its wrapped tokens to the holder.
+    fn convert(&mut self, amount:u64) -> Result<()>{
+
+        // Converting releases the backing of the from wrapper, so it follows the rules of
+        // `swap_to_source`, otherwise a successor without a lock or delay would bypass them
+        require!(
+            self.from_source_mint_account.unwrap_delay == 0,
+            TokenWrapperError::DelayedUnwrapRequired
+        );
+
+        self.from_source_mint_account.check_unlocked()?;
+        self.from_source_mint_account.check_not_paused()?;
+        self.to_source_mint_account.check_not_paused()?;
+
+        // Initiate burn
+        burn_wrapped(
+            &self.from_wrapped_token_program,
+            &self.from_wrapped_mint,
+            &self.from_holder_wrapped_mint_ata,
+            &self.holder,
+            amount
+        )?;
+
+        // Initiate the transfer of the backing from one vault to the other
+        transfer_from_vault(
+            &self.token_program,
+            &self.from_vault,
+            &self.to_vault,
+            &self.source_mint,
+            &self.from_vault_authority,
+            &self.from_source_mint_account,
+            amount
+        )?;
+
+        // Initiate mint
+        mint_wrapped(
+            &self.to_wrapped_token_program,
+            &self.to_wrapped_mint,
+            &self.to_holder_wrapped_mint_ata,
+            &self.to_mint_authority,
+            &self.to_source_mint_account,
+            amount
+        )?;
+
+        check_collateral(&mut self.from_vault, &mut self.from_wrapped_mint, &self.from_source_mint_account)?;
+        check_collateral(&mut self.to_vault, &mut self.to_wrapped_mint, &self.to_source_mint_account)
+    }
+}
+
+#[derive(Accounts)]
+pub struct BatchSwap<'info>{
+
+    buyer:Signer<'info>,
+
+    token_program:Program<'info, Token>,
+}
+
+impl<'info> BatchSwap<'info>{
+    /// Pairs each amount with its group of remaining accounts.
+    fn swap_groups<'a>(
+        &self,
+        remaining_accounts:&'info [AccountInfo<'info>],
+        amounts:&'a [u64]
+    ) -> Result<impl Iterator<Item = (&'info [AccountInfo<'info>], u64)> + 'a>
+    where 'info: 'a {
+
+        require!(
+            !amounts.is_empty() && remaining_accounts.len() == amounts.len() * SwapGroup::LEN,
+            TokenWrapperError::InvalidBatchAccounts
+        );
+
+        Ok(remaining_accounts.chunks_exact(SwapGroup::LEN).zip(amounts.iter().copied()))
+    }
+}
+
+/// The accounts of one wrapper in a batch swap, checked against the wrapper account the same way
+/// the `Swap` constraints are. The ATAs of the buyer have to exist already.
+struct SwapGroup<'info>{
+    buyer_mint_ata:Account<'info, TokenAccount>,
+    buyer_wrapped_mint_ata:InterfaceAccount<'info, token_interface::TokenAccount>,
+    vault:Account<'info, TokenAccount>,
+    /// The mint authority when wrapping and the vault authority when unwrapping. Only the PDA can
+    /// sign for the token program, so any other account makes the swap fail.
+    authority:UncheckedAccount<'info>,
+    source_mint:Account<'info, Mint>,
+    wrapped_mint:InterfaceAccount<'info, token_interface::Mint>,
+    source_mint_account:Account<'info, SourceMint>,
+    wrapped_token_program:Interface<'info, TokenInterface>,
+}
+
+impl<'info> SwapGroup<'info>{
+    pub const LEN:usize = 8;
+
+    fn load(accounts:&'info [AccountInfo<'info>], buyer:&Signer<'info>) -> Result<Self>{
+
+        let group = SwapGroup {
+            buyer_mint_ata: Account::try_from(&accounts[0])?,
+            buyer_wrapped_mint_ata: InterfaceAccount::try_from(&accounts[1])?,
+            vault: Account::try_from(&accounts[2])?,
+            authority: UncheckedAccount::try_from(&accounts[3]),
+            source_mint: Account::try_from(&accounts[4])?,
+            wrapped_mint: InterfaceAccount::try_from(&accounts[5])?,
+            source_mint_account: Account::try_from(&accounts[6])?,
+            wrapped_token_program: Interface::try_from(&accounts[7])?
+        };
+
+        // Only this program can create wrapper accounts, so the wrapper account is genuine and the
+        // other accounts only have to match it
+        let source_mint_account = &group.source_mint_account;
+
+        require_keys_eq!(group.source_mint.key(), source_mint_account.source_mint, TokenWrapperError::InvalidBatchAccounts);
+        require_keys_eq!(group.wrapped_mint.key(), source_mint_account.wrapped_mint, TokenWrapperError::InvalidBatchAccounts);
+        require_keys_eq!(group.vault.key(), source_mint_account.vault, TokenWrapperError::InvalidBatchAccounts);
+        require_keys_eq!(
+            group.wrapped_token_program.key(),
+            source_mint_account.wrapped_token_program,
+            TokenWrapperError::InvalidBatchAccounts
+        );
+
+        require_keys_eq!(group.buyer_mint_ata.mint, source_mint_account.source_mint, TokenWrapperError::InvalidBatchAccounts);
+        require_keys_eq!(group.buyer_mint_ata.owner, buyer.key(), TokenWrapperError::InvalidBatchAccounts);
+        require_keys_eq!(group.buyer_wrapped_mint_ata.mint, source_mint_account.wrapped_mint, TokenWrapperError::InvalidBatchAccounts);
+        require_keys_eq!(group.buyer_wrapped_mint_ata.owner, buyer.key(), TokenWrapperError::InvalidBatchAccounts);
+
+        Ok(group)
+    }
+
+    fn swap_to_wrapped(&mut self, buyer:&Signer<'info>, token_program:&Program<'info, Token>, amount:u64) -> Result<()>{
+
+        self.source_mint_account.check_not_paused()?;
+
+        let amount = swap_amount(amount, self.buyer_mint_ata.amount);
+
+        // Initiate the transfer of tokens from the user to the vault
+        transfer_to_vault(
+            token_program,
+            &self.buyer_mint_ata,
+            &self.vault,
+            &self.source_mint,
+            buyer,
+            amount
+        )?;
+
+        // Initiate mint
+        mint_wrapped(
+            &self.wrapped_token_program,
+            &self.wrapped_mint,
+            &self.buyer_wrapped_mint_ata,
+            &self.authority,
+            &self.source_mint_account,
+            amount
+        )?;
+
+        check_collateral(&mut self.vault, &mut self.wrapped_mint, &self.source_mint_account)
+    }
+
+    fn swap_to_source(&mut self, buyer:&Signer<'info>, token_program:&Program<'info, Token>, amount:u64) -> Result<()>{
+
+        // Delayed wrappers must go through `request_unwrap` and `claim_unwrap`
+        require!(
+            self.source_mint_account.unwrap_delay == 0,
+            TokenWrapperError::DelayedUnwrapRequired
+        );
+
+        self.source_mint_account.check_not_paused()?;
+        self.source_mint_account.check_unlocked()?;
+
+        let amount = swap_amount(amount, self.buyer_wrapped_mint_ata.amount);
+
+        // Initiate the transfer of tokens from the vault to the user
+        transfer_from_vault(
+            token_program,
+            &self.vault,
+            &self.buyer_mint_ata,
+            &self.source_mint,
+            &self.authority,
+            &self.source_mint_account,
+            amount
+        )?;
+
+        // Initiate burn
+        burn_wrapped(
+            &self.wrapped_token_program,
+            &self.wrapped_mint,
+            &self.buyer_wrapped_mint_ata,
+            buyer,
+            amount
+        )?;
+
+        check_collateral(&mut self.vault, &mut self.wrapped_mint, &self.source_mint_account)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct CreateMintArgs{
+    /// Unix timestamp before which `swap_to_source` is rejected, 0 for an unlocked wrapper.
+    pub unlock_timestamp:i64,
+    /// Seconds between `request_unwrap` and `claim_unwrap`, 0 allows a direct `swap_to_source`.
+    pub unwrap_delay:i64,
+    /// Create the wrapped mint with the Token-2022 non-transferable extension.
+    pub non_transferable:bool,
+    /// Create the wrapped mint with the Token-2022 interest-bearing extension at this rate in basis points.
+    pub interest_rate:Option<i16>,
+    /// Create the wrapped mint with a transfer hook into the allowlist hook program, managed by this authority.
+    pub allowlist_authority:Option<Pubkey>,
+    /// Give the wrapped mint a freeze authority PDA that this authority can use through `freeze_holder`.
+    pub compliance_authority:Option<Pubkey>,
+    /// How to treat a source mint that has a freeze authority.
+    pub source_freeze_policy:SourceFreezePolicy,
+    /// Authority over the wrapper-level settings, defaults to the payer.
+    pub authority:Option<Pubkey>,
+    /// Allowed to resume the wrapper after the circuit breaker paused it, defaults to the wrapper authority.
+    pub guardian:Option<Pubkey>,
+    /// Create the wrapped mint with the Token-2022 confidential transfer extension.
+    pub confidential_transfer:Option<ConfidentialTransferArgs>,
+    /// Tells apart wrappers of the same source mint, 0 for the first wrapper.
+    pub generation:u8
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ConfidentialTransferArgs{
+    /// Approves accounts and updates the configuration, none makes the configuration immutable.
+    pub authority:Option<Pubkey>,
+    /// Let new accounts use confidential transfers without approval by the authority.
+    pub auto_approve_new_accounts:bool,
+    /// ElGamal public key of an auditor that can decrypt the transferred amounts.
+    pub auditor_elgamal_pubkey:Option<[u8; 32]>
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SourceFreezePolicy{
+    /// Reject source mints with a freeze authority.
+    Reject,
+    /// Accept them and record the risk on the wrapper account.
+    #[default]
+    Record
+}
+
+impl CreateMintArgs{
+    pub fn generation_seed(&self) -> &[u8]{
+        generation_seed(&self.generation)
+    }
+
+    pub fn wrapped_mint_extensions(&self) -> Vec<ExtensionType>{
+        let mut extensions = Vec::new();
+
+        if self.non_transferable {
+            extensions.push(ExtensionType::NonTransferable);
+        }
+
+        if self.interest_rate.is_some() {
+            extensions.push(ExtensionType::InterestBearingConfig);
+        }
+
+        if self.allowlist_authority.is_some() {
+            extensions.push(ExtensionType::TransferHook);
+        }
+
+        if self.confidential_transfer.is_some() {
+            extensions.push(ExtensionType::ConfidentialTransferMint);
+        }
+
+        extensions
+    }
+}
+
+#[derive(InitSpace)]
+#[account(discriminator = 1)]
+pub struct SourceMint{
+    pub version:u8,
+    pub source_mint:Pubkey,
+    pub vault:Pubkey,
+    pub decimals:u8,
+    pub created_at_slot:u64,
+    pub wrapped_mint:Pubkey,
+    pub bump:u8,
+    pub unlock_timestamp:i64,
+    pub unwrap_delay:i64,
+    /// Source tokens owed to open unwrap tickets, their wrapped tokens are already burned.
+    pub pending_unwrap:u64,
+    /// Source tokens held in the vault for vesting schedules that have not been claimed yet.
+    pub vesting_outstanding:u64,
+    /// Allowed to update the wrapper-level settings.
+    pub authority:Pubkey,
+    /// Proposed authority that still has to accept the handover.
+    pub pending_authority:Option<Pubkey>,
+    /// Allowed to freeze and thaw wrapped token accounts.
+    pub compliance_authority:Pubkey,
+    /// The source mint has a freeze authority that can freeze the vault.
+    pub source_freezable:bool,
+    pub created_at:i64,
+    pub vault_authority_bump:u8,
+    pub mint_authority_bump:u8,
+    /// Token program of the source mint and the vault.
+    pub token_program:Pubkey,
+    /// Token program of the wrapped mint, either the token program or Token-2022.
+    pub wrapped_token_program:Pubkey,
+    /// Allowed to resume the wrapper once it is paused.
+    pub guardian:Pubkey,
+    /// Set by `verify_reserves` when the vault falls short of its required backing, halts
+    /// wraps and unwraps until the guardian resumes the wrapper.
+    pub paused:bool,
+    /// Tells apart wrappers of the same source mint, 0 for the first wrapper.
+    pub generation:u8,
+    /// Zeroed space for fields added by later versions without a realloc.
+    pub reserved:[u8; SourceMint::RESERVED_SPACE]
+}
+
+impl SourceMint{
+    pub const CURRENT_VERSION:u8 = 1;
+
+    pub const RESERVED_SPACE:usize = 20;
+
+    /// Size of the unversioned layout, a discriminator followed by the wrapped mint and the bump.
+    pub const LEGACY_SPACE:usize = SourceMint::DISCRIMINATOR.len() + 32 + 1;
+
+    /// Source tokens the vault has to hold, the wrapped supply plus the amounts owed to
+    /// unwrap tickets and vesting schedules.
+    pub fn required_backing(&self, wrapped_supply:u64) -> Result<u64>{
+        wrapped_supply.
+            checked_add(self.pending_unwrap).
+            and_then(|backing| backing.checked_add(self.vesting_outstanding)).
+            ok_or(TokenWrapperError::MathOverflow.into())
+    }
+
+    pub fn generation_seed(&self) -> &[u8]{
+        generation_seed(&self.generation)
+    }
+
+    /// Seeds of the vault authority, which signs for transfers out of the vault.
+    pub fn vault_authority_seeds(&self) -> [&[u8]; 4]{
+        [
+            b"vault-authority",
+            self.source_mint.as_ref(),
+            self.generation_seed(),
+            std::slice::from_ref(&self.vault_authority_bump)
+        ]
+    }
+
+    pub fn check_not_paused(&self) -> Result<()>{
+        require!(!self.paused, TokenWrapperError::WrapperPaused);
+        Ok(())
+    }
+
+    pub fn check_unlocked(&self) -> Result<()>{
+        // Time-locked wrappers only release the source tokens after the unlock timestamp
+        require!(
+            Clock::get()?.unix_timestamp >= self.unlock_timestamp,
+            TokenWrapperError::WrapperLocked
+        );
+        Ok(())
+    }
+}
+
+/// PDA seed that tells apart wrappers of the same source mint. The first wrapper has no seed so
+/// its addresses stay the same as before wrappers had generations.
+fn generation_seed(generation: &u8) -> &[u8]{
+    if *generation == 0 {
+        &[]
+    } else {
+        std::slice::from_ref(generation)
+    }
+}
+
+#[derive(InitSpace)]
+#[account(discriminator = 2)]
+pub struct UnwrapTicket{
+    pub owner:Pubkey,
+    pub amount:u64,
+    pub ready_at:i64,
+    pub bump:u8
+}
+
+#[derive(InitSpace)]
+#[account(discriminator = 3)]
+pub struct VestingSchedule{
+    pub beneficiary:Pubkey,
+    pub total_amount:u64,
+    pub claimed_amount:u64,
+    pub start:i64,
+    pub cliff:i64,
+    pub end:i64,
+    pub bump:u8
+}
+
+impl VestingSchedule{
+    /// Amount unlocked at `now`, nothing before the cliff and linear from `start` to `end`.
+    pub fn vested_amount(&self, now:i64) -> Result<u64>{
+        if now < self.cliff {
+            return Ok(0);
+        }
+
+        if now >= self.end {
+            return Ok(self.total_amount);
+        }
+
+        let elapsed = now.checked_sub(self.start).ok_or(TokenWrapperError::MathOverflow)? as u128;
+        let duration = self.end.checked_sub(self.start).ok_or(TokenWrapperError::MathOverflow)? as u128;
+
+        let vested_amount = (self.total_amount as u128).
+            checked_mul(elapsed).ok_or(TokenWrapperError::MathOverflow)? / duration;
+
+        Ok(vested_amount as u64)
+    }
+}
+
+#[derive(InitSpace)]
+#[account(discriminator = 4)]
+pub struct Config{
+    pub admin:Pubkey,
+    pub creation_policy:CreationPolicy,
+    /// Lamports charged by `create_mint` and paid to the treasury.
+    pub creation_fee_lamports:u64,
+    pub treasury:Pubkey,
+    pub bump:u8
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum CreationPolicy{
+    /// Anyone can create a wrapper for any source mint.
+    Open,
+    /// Only the config admin can create wrappers.
+    AdminOnly,
+    /// Anyone can create a wrapper for a source mint allowlisted by the admin.
+    Allowlisted
+}
+
+#[derive(InitSpace)]
+#[account(discriminator = 5)]
+pub struct AllowedSourceMint{
+    pub bump:u8
+}
+
+/// Wrapper of the same source mint that holders of a wrapper can move to through `migrate_holder`.
+#[derive(InitSpace)]
+#[account(discriminator = 6)]
+pub struct SuccessorLink{
+    pub successor:Pubkey,
+    pub bump:u8
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ReservesReport{
+    pub vault_amount:u64,
+    pub wrapped_supply:u64,
+    /// Wrapped supply plus the amounts owed to unwrap tickets and vesting schedules.
+    pub required_backing:u64,
+    pub fully_backed:bool
+}
+
+#[event]
+pub struct ReservesVerified{
+    pub source_mint:Pubkey,
+    pub wrapped_mint:Pubkey,
+    pub slot:u64,
+    pub report:ReservesReport
+}
+
+#[event]
+pub struct WrapperPaused{
+    pub source_mint:Pubkey,
+    pub wrapped_mint:Pubkey,
+    pub slot:u64,
+    pub vault_amount:u64,
+    pub required_backing:u64
+}
+
+#[error_code]
+pub enum TokenWrapperError{
+    #[msg("The unlock timestamp cannot be negative")]
+    InvalidUnlockTimestamp,
+    #[msg("The wrapper is locked until its unlock timestamp")]
+    WrapperLocked,
+    #[msg("The unwrap delay cannot be negative")]
+    InvalidUnwrapDelay,
+    #[msg("This wrapper only allows unwrapping through request_unwrap and claim_unwrap")]
+    DelayedUnwrapRequired,
+    #[msg("The unwrap ticket is not ready to be claimed yet")]
+    UnwrapNotReady,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("The amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("The vesting schedule must satisfy start <= cliff <= end and start < end, and end - start must fit in an i64")]
+    InvalidVestingSchedule,
+    #[msg("There are no vested tokens to claim")]
+    NothingToClaim,
+    #[msg("Wrapped mint extensions require the Token-2022 program")]
+    ExtensionsRequireToken2022,
+    #[msg("The allowlist hook program, extra account meta list and allowlist config accounts are required")]
+    MissingAllowlistAccounts,
+    #[msg("The source mint has a freeze authority")]
+    SourceMintFreezable,
+    #[msg("The vault has been frozen by the source mint freeze authority")]
+    VaultFrozen,
+    #[msg("The signer is not allowed to perform this action")]
+    Unauthorized,
+    #[msg("The source mint is not allowlisted for wrapper creation")]
+    SourceMintNotAllowlisted,
+    #[msg("The wrapper account is not in the legacy layout")]
+    WrapperAlreadyMigrated,
+    #[msg("The vault holds less than the wrapped supply it backs")]
+    UndercollateralizedVault,
+    #[msg("The vault holds no tokens above its required backing")]
+    NoSurplus,
+    #[msg("The owner is neither the vault authority nor the mint authority of the wrapper")]
+    InvalidRecoveryOwner,
+    #[msg("Source tokens held by the wrapper can only leave through unwraps and surplus sweeps")]
+    SourceMintNotRecoverable,
+    #[msg("The wrapper is paused until its guardian resumes it")]
+    WrapperPaused,
+    #[msg("The wrapper is not paused")]
+    WrapperNotPaused,
+    #[msg("The wrapper still has wrapped supply, outstanding claims or tokens in its vault")]
+    WrapperNotEmpty,
+    #[msg("The wrapper is not the successor of the wrapper being migrated from")]
+    InvalidSuccessor,
+    #[msg("The remaining accounts do not match the batch amounts or the wrappers they belong to")]
+    InvalidBatchAccounts
 }
\ No newline at end of file
@@ -3,14 +3,27 @@ mod tests {
 use std::collections::HashMap;
 use anyhow::Result as AnyResult;
 use anchor_lang::{
-    InstructionData, prelude::{AccountMeta, Pubkey}, solana_program::{
+    AccountDeserialize, AccountSerialize, AnchorSerialize, Discriminator, InstructionData, error::ErrorCode as AnchorErrorCode,
+    prelude::{AccountMeta, Pubkey}, solana_program::{
         instruction::Instruction, program_error::ProgramError, system_instruction::SystemError
     }, system_program
 };
-use anchor_spl::{associated_token::get_associated_token_address, token::spl_token::error::TokenError};
+use allowlist_hook::AllowlistHookError;
+use anchor_spl::{
+    associated_token::{get_associated_token_address, get_associated_token_address_with_program_id},
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            BaseStateWithExtensions, StateWithExtensions,
+            confidential_transfer::ConfidentialTransferMint,
+            interest_bearing_mint::InterestBearingConfig
+        }
+    },
+    token::spl_token::error::TokenError
+};
 use spl_token::state::{Account as TokenAccount, GenericTokenAccount, Mint};
 use solana_sdk::{
-    account::Account, program_option::COption::None as CNone, 
+    account::Account, program_option::{COption, COption::None as CNone}, 
     program_pack::Pack, rent::Rent, signature::Keypair, signer::Signer
 };
 use mollusk_svm::{
@@ -19,10 +32,47 @@ use mollusk_svm::{
 use mollusk_svm_programs_token::{
     token::{create_account_for_mint, create_account_for_token_account}
 };
-use token_wrapper::instruction::{
-    CreateMint,
-    SwapToWrapped,
-    SwapToSource
+use token_wrapper::{
+    SWAP_ALL,
+    Config,
+    ConfidentialTransferArgs,
+    CreateMintArgs,
+    CreationPolicy,
+    ReservesReport,
+    SourceMint,
+    SourceFreezePolicy,
+    TokenWrapperError,
+    instruction::{
+        CreateMint,
+        InitializeConfig,
+        SetCreationPolicy,
+        AllowSourceMint,
+        DisallowSourceMint,
+        SetCreationFee,
+        SwapToWrapped,
+        SwapToSource,
+        RequestUnwrap,
+        ClaimUnwrap,
+        CreateVesting,
+        ClaimVested,
+        UpdateInterestRate,
+        FreezeHolder,
+        ThawHolder,
+        TransferWrapperAuthority,
+        AcceptWrapperAuthority,
+        MigrateWrapper,
+        VerifyReserves,
+        SweepSurplus,
+        RecoverForeignTokens,
+        ResumeWrapper,
+        SetGuardian,
+        CloseWrapper,
+        SetSuccessor,
+        MigrateHolder,
+        Convert,
+        BatchSwapToWrapped,
+        BatchSwapToSource
+    }
 };
 
 fn make_check_swapped_to_wrapped(
@@ -120,6 +170,22 @@ fn make_check_swapped_to_source(
     }
 }
 
+fn token_amount(accounts: &[(Pubkey, Account)], token_account: Pubkey) -> u64 {
+    accounts
+        .iter()
+        .find(|(key, _)| token_account.eq(key))
+        .map(|(_, account)| TokenAccount::unpack(&account.data[..TokenAccount::LEN])
+            .expect("Invalid token account").amount)
+        .expect("Could not find token account")
+}
+
+fn find_source_mint(accounts: &[(Pubkey, Account)], source_mint_exists: Pubkey) -> Option<SourceMint> {
+    accounts
+        .iter()
+        .find(|(key, _)| source_mint_exists.eq(key))
+        .and_then(|(_, account)| SourceMint::try_deserialize(&mut &account.data[..]).ok())
+}
+
 #[test]
  fn passing_test_1() {
     
@@ -227,199 +293,2096 @@ fn make_check_swapped_to_source(
 
 }
 
-pub struct TokenWrapperAccountStore{
-    pub accounts_map:HashMap<Pubkey, Account>
-}
+#[test]
+fn locked_wrapper_test() {
 
-impl TokenWrapperAccountStore{
-    pub fn default()->Self{
-        TokenWrapperAccountStore{ 
-            accounts_map: HashMap::<Pubkey, Account>::default() 
-        }
-    }
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
 
-    pub fn get_account_or_default(&self, account_key: &Pubkey)->Account{
-        self.get_account(account_key).
-            unwrap_or(self.default_account(&Pubkey::default()))
-    }
-}
+    let mut program_test = TokenWrapperTest::new();
 
-impl AccountStore for TokenWrapperAccountStore{
+    let mut account_store = TokenWrapperAccountStore::default();
 
-    fn get_account(&self, account_key: &Pubkey) -> Option<Account>{
-        self.accounts_map.get(account_key).cloned()
-    }
+    program_test.setup_default(&mut account_store);
 
-    #[inline(always)]
-    fn store_account(&mut self, account_key: Pubkey, account: Account){
-        self.accounts_map.insert(account_key, account);
-    }
-}
+    let mollusk_context = mollusk.with_context(account_store);
 
-pub enum SwapType{
-    SwapToWrapped,
-    SwapToSource
+    // Wrapping is allowed at any time but unwrapping is rejected before the unlock timestamp
+    let locked_args = CreateMintArgs{
+        unlock_timestamp: i64::MAX,
+        ..CreateMintArgs::default()
+    };
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction_with_args(locked_args), &[Check::success()]),
+            (
+                &program_test.get_swap_instruction(SwapType::SwapToWrapped),
+                &[Check::success(), Check::custom(make_check_swapped_to_wrapped(
+                    program_test.buyer_mint_ata,
+                    program_test.buyer_wrapped_mint_ata,
+                    program_test.original_source_amount,
+                    program_test.original_wrapped_amount,
+                    program_test.wrap_amount,
+                ), "check_swapped_to_wrapped")],
+            ),
+            (
+                &program_test.get_swap_instruction(SwapType::SwapToSource),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::WrapperLocked.into()))]
+            ),
+        ]
+    );
 }
 
-pub struct TokenWrapperTest {
+#[test]
+fn delayed_unwrap_test() {
 
-    // Signers
-    pub payer: Keypair,        
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
 
-    // Mint accounts
-    pub source_mint: Keypair,  
-    pub wrapped_mint: Keypair, 
+    let mut program_test = TokenWrapperTest::new();
 
-    // PDAs
-    pub mint_authority: Pubkey,
-    pub vault_authority: Pubkey,        
-    pub source_mint_exists: Pubkey,     
+    let mut account_store = TokenWrapperAccountStore::default();
 
-    // Token accounts
-    pub vault: Pubkey,                  
-    pub buyer_mint_ata: Pubkey,         
-    pub buyer_wrapped_mint_ata: Pubkey, 
+    program_test.setup_default(&mut account_store);
 
-    // Data 
-    pub original_source_amount:u64,
-    pub original_wrapped_amount:u64,
-    pub wrap_amount:u64,
-    pub source_amount:u64,
+    let mut mollusk_context = mollusk.with_context(account_store);
+
+    // A direct unwrap is rejected, the request goes through but the ticket cannot be claimed
+    // before the delay has passed
+    let unwrap_delay = 60 * 60 * 24;
+
+    let delayed_args = CreateMintArgs{
+        unwrap_delay,
+        ..CreateMintArgs::default()
+    };
+
+    let buyer_mint_ata = program_test.buyer_mint_ata;
+    let buyer_wrapped_mint_ata = program_test.buyer_wrapped_mint_ata;
+    let source_mint_exists = program_test.source_mint_exists;
+    let unwrap_ticket = program_test.get_unwrap_ticket(0);
+    let original_source_amount = program_test.original_source_amount;
+    let wrap_amount = program_test.wrap_amount;
+    let unwrap_amount = program_test.source_amount;
+
+    let check_pending_unwrap = move |pending_unwrap: u64| {
+        move |accounts: &[(Pubkey, Account)]| {
+            find_source_mint(accounts, source_mint_exists).is_some_and(|source_mint_account|
+                source_mint_account.pending_unwrap == pending_unwrap
+            )
+        }
+    };
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction_with_args(delayed_args), &[Check::success()]),
+            (&program_test.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]),
+            (
+                &program_test.get_swap_instruction(SwapType::SwapToSource),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::DelayedUnwrapRequired.into()))]
+            ),
+            (
+                &program_test.get_request_unwrap_instruction(0),
+                &[
+                    Check::success(),
+                    Check::custom(check_pending_unwrap(unwrap_amount), "check_unwrap_requested"),
+                    Check::custom(move |accounts: &[(Pubkey, Account)]| {
+                        token_amount(accounts, buyer_wrapped_mint_ata) == wrap_amount - unwrap_amount
+                    }, "check_wrapped_burned")
+                ]
+            ),
+            (
+                &program_test.get_claim_unwrap_instruction(0),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::UnwrapNotReady.into()))]
+            ),
+        ]
+    );
+
+    // Once the delay has passed the ticket pays out the backing and is closed
+    mollusk_context.mollusk.sysvars.clock.unix_timestamp += unwrap_delay;
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_claim_unwrap_instruction(0),
+                &[
+                    Check::success(),
+                    Check::custom(check_pending_unwrap(0), "check_unwrap_claimed"),
+                    Check::custom(move |accounts: &[(Pubkey, Account)]| {
+                        token_amount(accounts, buyer_mint_ata) == original_source_amount - wrap_amount + unwrap_amount &&
+                            accounts
+                                .iter()
+                                .find(|(key, _)| unwrap_ticket.eq(key))
+                                .is_some_and(|(_, account)| account.lamports == 0)
+                    }, "check_ticket_paid_and_closed")
+                ]
+            ),
+        ]
+    );
 }
 
-impl TokenWrapperTest {
+#[test]
+fn vesting_test() {
 
-     pub fn new() -> Self {
-        let payer = Keypair::new();
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
 
-        let source_mint = Keypair::new();
-        let wrapped_mint = Keypair::new();
+    let mut program_test = TokenWrapperTest::new();
 
-        let (mint_authority, _) =
-            Pubkey::find_program_address(
-                &[b"mint-authority", wrapped_mint.pubkey().as_ref()],
-                &token_wrapper::ID,
-            );
-        let (vault_authority, _) =
-            Pubkey::find_program_address(
-                &[b"vault-authority", source_mint.pubkey().as_ref()],
-                &token_wrapper::ID,
-            );
-        let (source_mint_exists, _) =
-            Pubkey::find_program_address(
-                &[b"mint", source_mint.pubkey().as_ref()],
-                &token_wrapper::ID,
-            );
+    let mut account_store = TokenWrapperAccountStore::default();
 
-        let vault = get_associated_token_address(&vault_authority, &source_mint.pubkey());
-        let buyer_mint_ata = get_associated_token_address(&payer.pubkey(), &source_mint.pubkey());
-        let buyer_wrapped_mint_ata =
-            get_associated_token_address(&payer.pubkey(), &wrapped_mint.pubkey());
+    program_test.setup_default(&mut account_store);
 
-        Self {
-            payer,
+    let mut mollusk_context = mollusk.with_context(account_store);
+
+    // Nothing unlocks before the cliff, then linearly from the start until the end
+    let vesting_amount = 1_000;
+    let start = mollusk_context.mollusk.sysvars.clock.unix_timestamp;
+    let cliff = start + 100;
+    let end = start + 1_000;
+
+    let buyer_mint_ata = program_test.buyer_mint_ata;
+    let source_mint_exists = program_test.source_mint_exists;
+    let funder = program_test.payer.pubkey();
+    let vesting_schedule = program_test.get_vesting_schedule(&program_test.payer.pubkey(), 0);
+    let original_source_amount = program_test.original_source_amount;
+
+    let check_vesting = move |claimed_amount: u64| {
+        move |accounts: &[(Pubkey, Account)]| {
+            token_amount(accounts, buyer_mint_ata) == original_source_amount - vesting_amount + claimed_amount &&
+                find_source_mint(accounts, source_mint_exists).is_some_and(|source_mint_account|
+                    source_mint_account.vesting_outstanding == vesting_amount - claimed_amount
+                )
+        }
+    };
 
-            source_mint,
-            wrapped_mint,
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction(), &[Check::success()]),
+            (
+                &program_test.get_create_vesting_instruction(funder, 0, vesting_amount, start, cliff, end),
+                &[Check::success(), Check::custom(check_vesting(0), "check_vesting_created")]
+            ),
+        ]
+    );
 
-            mint_authority,
-            vault_authority,
-            source_mint_exists,
+    mollusk_context.mollusk.sysvars.clock.unix_timestamp = cliff - 1;
 
-            vault,
-            buyer_mint_ata,
-            buyer_wrapped_mint_ata,
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_claim_vested_instruction(0),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::NothingToClaim.into()))]
+            ),
+        ]
+    );
 
-            original_source_amount:0,
-            original_wrapped_amount:0,
+    mollusk_context.mollusk.sysvars.clock.unix_timestamp = cliff;
 
-            wrap_amount:0,
-            source_amount:0
-        }
-    }
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_claim_vested_instruction(0),
+                &[Check::success(), Check::custom(check_vesting(100), "check_claimed_at_cliff")]
+            ),
+        ]
+    );
 
-    #[inline(always)]
-    pub fn _generate_keys(&mut self){
-        *self = TokenWrapperTest::new();
-    }
+    mollusk_context.mollusk.sysvars.clock.unix_timestamp = start + 500;
 
-    pub fn get_mollusk_with_programs() -> Mollusk{
-        let mut runtime = Mollusk::new(&token_wrapper::ID, "token_wrapper");
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_claim_vested_instruction(0),
+                &[Check::success(), Check::custom(check_vesting(500), "check_claimed_linearly")]
+            ),
+            (
+                &program_test.get_claim_vested_instruction(0),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::NothingToClaim.into()))]
+            ),
+        ]
+    );
 
-        mollusk_svm_programs_token::token::add_program(&mut runtime);
-        
-        mollusk_svm_programs_token::associated_token::add_program(&mut runtime);
+    mollusk_context.mollusk.sysvars.clock.unix_timestamp = end;
 
-        runtime
-    }
-        
-    pub fn setup(&mut self, accounts: &mut TokenWrapperAccountStore, buyer_lamports: u64,
-                mint_supply: u64, token_account_balance: u64, wrap_amount:u64, source_amount:u64){
-        // Add the payer account
-        let rent = Rent::default();
+    // The fully claimed schedule is closed, so the beneficiary can be granted a new one
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_claim_vested_instruction(0),
+                &[
+                    Check::success(),
+                    Check::custom(check_vesting(vesting_amount), "check_claimed_at_end"),
+                    Check::custom(move |accounts: &[(Pubkey, Account)]| {
+                        accounts
+                            .iter()
+                            .find(|(key, _)| vesting_schedule.eq(key))
+                            .is_some_and(|(_, account)| account.lamports == 0)
+                    }, "check_vesting_closed")
+                ]
+            ),
+            (
+                &program_test.get_create_vesting_instruction(funder, 0, vesting_amount, end, end, end + 1_000),
+                &[Check::success()]
+            ),
+        ]
+    );
+}
 
-        let buyer_minimum_balance = rent.minimum_balance(0);
-        let buyer_total_lamports = buyer_lamports.max(buyer_minimum_balance);
-        
-        accounts.store_account(
-            self.payer.pubkey(), 
-            Account::new(buyer_total_lamports, 0, &system_program::ID)
-        );
+#[test]
+fn hostile_vesting_test() {
 
-        // Add the source mint account
-        let mint_data = Mint {
-            mint_authority: CNone,
-            supply: mint_supply,
-            decimals: 9,
-            is_initialized: true,
-            freeze_authority: CNone
-        };
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
 
-        accounts.store_account(
-            self.source_mint.pubkey(), 
-            create_account_for_mint(mint_data)
-        );
+    let mut program_test = TokenWrapperTest::new();
 
-        // Add the user token account
-        let token_account_data = TokenAccount{
-            mint: self.source_mint.pubkey(),
-            owner: self.payer.pubkey(),
-            amount: token_account_balance,
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    // Anyone can fund a schedule for the payer, here one that never finishes vesting
+    let attacker = Pubkey::new_unique();
+
+    account_store.store_account(
+        attacker,
+        Account::new(1_000_000_000, 0, &system_program::ID)
+    );
+
+    account_store.store_account(
+        get_associated_token_address(&attacker, &program_test.source_mint.pubkey()),
+        create_account_for_token_account(TokenAccount{
+            mint: program_test.source_mint.pubkey(),
+            owner: attacker,
+            amount: 1,
             delegate: CNone,
             state: spl_token::state::AccountState::Initialized,
             is_native: CNone,
             delegated_amount: 0,
             close_authority: CNone
-        };
-
-        accounts.store_account(
-            self.buyer_mint_ata, 
-            create_account_for_token_account(token_account_data)
-        );
+        })
+    );
 
-        // Add the programs
-        let token_key_account_pair = 
-            mollusk_svm_programs_token::token::keyed_account();
-        let associated_token_key_account_pair = 
-            mollusk_svm_programs_token::associated_token::keyed_account();
-        let system_program_key_account_pair = 
-            keyed_account_for_system_program();
-        
-        accounts.store_account(
-            token_key_account_pair.0,
-            token_key_account_pair.1
-        );
+    let mut mollusk_context = mollusk.with_context(account_store);
 
-        accounts.store_account(
-            associated_token_key_account_pair.0, 
-            associated_token_key_account_pair.1
-        );
+    let funder = program_test.payer.pubkey();
+    let start = mollusk_context.mollusk.sysvars.clock.unix_timestamp;
+    let end = start + 1_000;
 
-        accounts.store_account(
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction(), &[Check::success()]),
+            (
+                &program_test.get_create_vesting_instruction(attacker, 0, 1, start, start, i64::MAX),
+                &[Check::success()]
+            ),
+            // The schedule id is taken, but the beneficiary is not locked out of the wrapper
+            (
+                &program_test.get_create_vesting_instruction(funder, 0, 1_000, start, start, end),
+                &[Check::err(ProgramError::Custom(SystemError::AccountAlreadyInUse as u32))]
+            ),
+            (&program_test.get_create_vesting_instruction(funder, 1, 1_000, start, start, end), &[Check::success()]),
+            // A schedule whose duration overflows is rejected instead of panicking on every claim
+            (
+                &program_test.get_create_vesting_instruction(funder, 2, 1_000, i64::MIN, 0, i64::MAX),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::InvalidVestingSchedule.into()))]
+            ),
+        ]
+    );
+
+    mollusk_context.mollusk.sysvars.clock.unix_timestamp = end;
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_claim_vested_instruction(1), &[Check::success()]),
+            (
+                &program_test.get_claim_vested_instruction(0),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::NothingToClaim.into()))]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn non_transferable_wrapper_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    let non_transferable_args = CreateMintArgs{
+        non_transferable: true,
+        ..CreateMintArgs::default()
+    };
+
+    // The non-transferable extension is only available under Token-2022
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_create_mint_instruction_with_args(non_transferable_args.clone()),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::ExtensionsRequireToken2022.into()))]
+            ),
+        ]
+    );
+
+    // Wrapped receipts can still be minted and burned by the depositor
+    program_test.use_token_2022_wrapped_mint();
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction_with_args(non_transferable_args), &[Check::success()]),
+            (&program_test.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]),
+            (&program_test.get_swap_instruction(SwapType::SwapToSource), &[Check::success()]),
+        ]
+    );
+}
+
+#[test]
+fn freezable_source_mint_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    // Give the source mint a freeze authority
+    let mint_data = Mint {
+        mint_authority: CNone,
+        supply: 1_000_000_000_000_000,
+        decimals: 9,
+        is_initialized: true,
+        freeze_authority: COption::Some(Pubkey::new_unique())
+    };
+
+    account_store.store_account(
+        program_test.source_mint.pubkey(), 
+        create_account_for_mint(mint_data)
+    );
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    let reject_args = CreateMintArgs{
+        source_freeze_policy: SourceFreezePolicy::Reject,
+        ..CreateMintArgs::default()
+    };
+
+    let source_mint_exists = program_test.source_mint_exists;
+
+    // The default policy records the risk on the wrapper
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_create_mint_instruction_with_args(reject_args),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::SourceMintFreezable.into()))]
+            ),
+            (
+                &program_test.get_create_mint_instruction(),
+                &[Check::success(), Check::custom(move |accounts: &[(Pubkey, Account)]| {
+                    find_source_mint(accounts, source_mint_exists).
+                        is_some_and(|source_mint_account| source_mint_account.source_freezable)
+                }, "check_source_freezable")]
+            ),
+            (&program_test.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]),
+        ]
+    );
+
+    // The source mint freeze authority freezes the vault
+    program_test.store_vault(
+        &mut mollusk_context.account_store.borrow_mut(),
+        program_test.wrap_amount,
+        spl_token::state::AccountState::Frozen
+    );
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_swap_instruction(SwapType::SwapToSource),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::VaultFrozen.into()))]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn confidential_transfer_wrapper_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+    program_test.use_token_2022_wrapped_mint();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    let confidential_args = CreateMintArgs{
+        confidential_transfer: Some(ConfidentialTransferArgs{
+            authority: Some(program_test.admin.pubkey()),
+            auto_approve_new_accounts: true,
+            auditor_elgamal_pubkey: None
+        }),
+        ..CreateMintArgs::default()
+    };
+
+    let wrapped_mint = program_test.wrapped_mint.pubkey();
+    let confidential_transfer_authority = program_test.admin.pubkey();
+
+    // Public swaps keep working on a wrapped mint with the confidential transfer extension
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_create_mint_instruction_with_args(confidential_args),
+                &[Check::success(), Check::custom(move |accounts: &[(Pubkey, Account)]| {
+                    accounts
+                        .iter()
+                        .find(|(key, _)| wrapped_mint.eq(key))
+                        .is_some_and(|(_, account)| {
+                            let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&account.data).
+                                expect("Invalid wrapped mint");
+
+                            mint.get_extension::<ConfidentialTransferMint>().is_ok_and(|config|
+                                Option::<Pubkey>::from(config.authority) == Some(confidential_transfer_authority) &&
+                                    bool::from(config.auto_approve_new_accounts)
+                            )
+                        })
+                }, "check_confidential_transfer_mint")]
+            ),
+            (
+                &program_test.get_swap_instruction(SwapType::SwapToWrapped),
+                &[Check::success(), Check::custom(make_check_swapped_to_wrapped(
+                    program_test.buyer_mint_ata,
+                    program_test.buyer_wrapped_mint_ata,
+                    program_test.original_source_amount,
+                    program_test.original_wrapped_amount,
+                    program_test.wrap_amount,
+                ), "check_swapped_to_wrapped")],
+            ),
+            (
+                &program_test.get_swap_instruction(SwapType::SwapToSource),
+                &[Check::success(), Check::custom(make_check_swapped_to_source(
+                    program_test.buyer_mint_ata,
+                    program_test.buyer_wrapped_mint_ata,
+                    program_test.original_source_amount,
+                    program_test.original_wrapped_amount,
+                    program_test.wrap_amount,
+                    program_test.source_amount,
+                ), "check_swapped_to_source")],
+            ),
+        ]
+    );
+}
+
+#[test]
+fn interest_rate_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+    program_test.use_token_2022_wrapped_mint();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    let interest_args = CreateMintArgs{
+        interest_rate: Some(500),
+        ..CreateMintArgs::default()
+    };
+
+    let wrapped_mint = program_test.wrapped_mint.pubkey();
+
+    let check_rate = move |rate: i16| {
+        move |accounts: &[(Pubkey, Account)]| {
+            accounts
+                .iter()
+                .find(|(key, _)| wrapped_mint.eq(key))
+                .is_some_and(|(_, account)| {
+                    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&account.data).
+                        expect("Invalid wrapped mint");
+
+                    mint.get_extension::<InterestBearingConfig>().
+                        is_ok_and(|config| i16::from(config.current_rate) == rate)
+                })
+        }
+    };
+
+    // Only the wrapper authority, the payer here, can change the rate
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_create_mint_instruction_with_args(interest_args),
+                &[Check::success(), Check::custom(check_rate(500), "check_initial_rate")]
+            ),
+            (
+                &program_test.get_update_interest_rate_instruction(program_test.admin.pubkey(), 1_000),
+                &[Check::err(ProgramError::Custom(AnchorErrorCode::ConstraintHasOne.into()))]
+            ),
+            (
+                &program_test.get_update_interest_rate_instruction(program_test.payer.pubkey(), 1_000),
+                &[Check::success(), Check::custom(check_rate(1_000), "check_updated_rate")]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn freeze_holder_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    // The admin is the compliance authority of the wrapper, the payer is the holder
+    let compliance_authority = program_test.admin.pubkey();
+
+    let compliance_args = CreateMintArgs{
+        compliance_authority: Some(compliance_authority),
+        ..CreateMintArgs::default()
+    };
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction_with_args(compliance_args), &[Check::success()]),
+            (&program_test.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]),
+            (
+                &program_test.get_compliance_instruction(program_test.payer.pubkey(), true),
+                &[Check::err(ProgramError::Custom(AnchorErrorCode::ConstraintHasOne.into()))]
+            ),
+            (&program_test.get_compliance_instruction(compliance_authority, true), &[Check::success()]),
+            // A frozen holder cannot burn the wrapped tokens to unwrap
+            (&program_test.get_swap_instruction(SwapType::SwapToSource), &[Check::err(TokenError::AccountFrozen.into())]),
+            (&program_test.get_compliance_instruction(compliance_authority, false), &[Check::success()]),
+            (&program_test.get_swap_instruction(SwapType::SwapToSource), &[Check::success()]),
+        ]
+    );
+}
+
+#[test]
+fn creation_policy_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    // A wrapper of another source mint paid for by the admin
+    let mut admin_test = TokenWrapperTest::new_wrapper(
+        program_test.admin.insecure_clone(),
+        program_test.admin.insecure_clone(),
+        program_test.treasury,
+        Keypair::new(),
+        0
+    );
+    admin_test.setup_default(&mut account_store);
+
+    program_test.store_config(&mut account_store, CreationPolicy::AdminOnly);
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    // Only the admin can create wrappers
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_create_mint_instruction(),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::Unauthorized.into()))]
+            ),
+            (&admin_test.get_create_mint_instruction(), &[Check::success()]),
+        ]
+    );
+
+    // Allowlisted sources need an allowed source mint entry
+    let mut account_store = mollusk_context.account_store.borrow_mut();
+
+    program_test.store_config(&mut account_store, CreationPolicy::Allowlisted);
+
+    core::mem::drop(account_store);
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_create_mint_instruction(),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::SourceMintNotAllowlisted.into()))]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn allowlisted_creation_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    // The admin pays for the allowed source mint entries
+    account_store.store_account(
+        program_test.admin.pubkey(),
+        Account::new(1_000_000_000, 0, &system_program::ID)
+    );
+
+    // The next generation wrapper of the source mint, created after the source mint is disallowed
+    let successor_test = program_test.successor();
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    let admin = program_test.admin.pubkey();
+    let payer = program_test.payer.pubkey();
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_set_creation_policy_instruction(payer, CreationPolicy::Allowlisted),
+                &[Check::err(ProgramError::Custom(AnchorErrorCode::ConstraintHasOne.into()))]
+            ),
+            (&program_test.get_set_creation_policy_instruction(admin, CreationPolicy::Allowlisted), &[Check::success()]),
+            (
+                &program_test.get_create_mint_instruction(),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::SourceMintNotAllowlisted.into()))]
+            ),
+            (
+                &program_test.get_allow_source_mint_instruction(payer, true),
+                &[Check::err(ProgramError::Custom(AnchorErrorCode::ConstraintHasOne.into()))]
+            ),
+            (&program_test.get_allow_source_mint_instruction(admin, true), &[Check::success()]),
+            (&program_test.get_create_mint_instruction_with_allowed_source(), &[Check::success()]),
+            (&program_test.get_allow_source_mint_instruction(admin, false), &[Check::success()]),
+            (
+                &successor_test.get_create_mint_instruction(),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::SourceMintNotAllowlisted.into()))]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn initialize_config_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    // Start without a config, the program is deployed by a separate upgrade authority
+    account_store.accounts_map.remove(&program_test.config);
+
+    let upgrade_authority = Pubkey::new_unique();
+
+    account_store.store_account(
+        upgrade_authority,
+        Account::new(1_000_000_000, 0, &system_program::ID)
+    );
+
+    program_test.store_program_data(&mut account_store, upgrade_authority);
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    let config = program_test.config;
+    let admin = program_test.admin.pubkey();
+    let treasury = program_test.treasury;
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_initialize_config_instruction(program_test.payer.pubkey(), CreationPolicy::AdminOnly),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::Unauthorized.into()))]
+            ),
+            (
+                &program_test.get_initialize_config_instruction(upgrade_authority, CreationPolicy::AdminOnly),
+                &[Check::success(), Check::custom(move |accounts: &[(Pubkey, Account)]| {
+                    accounts
+                        .iter()
+                        .find(|(key, _)| config.eq(key))
+                        .and_then(|(_, account)| Config::try_deserialize(&mut &account.data[..]).ok())
+                        .is_some_and(|config| config.admin == admin &&
+                            config.creation_policy == CreationPolicy::AdminOnly &&
+                            config.creation_fee_lamports == 0 &&
+                            config.treasury == treasury
+                        )
+                }, "check_config_initialized")]
+            ),
+            (
+                &program_test.get_create_mint_instruction(),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::Unauthorized.into()))]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn creation_fee_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    // A wrapper paid for by the admin, and one created after the fee and treasury change
+    let mut admin_test = TokenWrapperTest::new_wrapper(
+        program_test.admin.insecure_clone(),
+        program_test.admin.insecure_clone(),
+        program_test.treasury,
+        Keypair::new(),
+        0
+    );
+    admin_test.setup_default(&mut account_store);
+
+    let new_treasury = Pubkey::new_unique();
+
+    let mut later_test = TokenWrapperTest::new_wrapper(
+        program_test.payer.insecure_clone(),
+        program_test.admin.insecure_clone(),
+        new_treasury,
+        Keypair::new(),
+        0
+    );
+    later_test.setup_default(&mut account_store);
+
+    let creation_fee_lamports = 1_000_000_000;
+    let new_creation_fee_lamports = 2 * creation_fee_lamports;
+
+    program_test.store_config_with_fee(&mut account_store, CreationPolicy::Open, creation_fee_lamports);
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    let treasury = program_test.treasury;
+    let config = program_test.config;
+    let admin = program_test.admin.pubkey();
+
+    let check_treasury = move |treasury: Pubkey, lamports: u64| {
+        move |accounts: &[(Pubkey, Account)]| {
+            accounts
+                .iter()
+                .find(|(key, _)| treasury.eq(key))
+                .is_some_and(|(_, account)| account.lamports == lamports)
+        }
+    };
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_create_mint_instruction(),
+                &[Check::success(), Check::custom(check_treasury(treasury, creation_fee_lamports), "check_creation_fee")]
+            ),
+            // The admin is not charged
+            (
+                &admin_test.get_create_mint_instruction(),
+                &[Check::success(), Check::custom(check_treasury(treasury, creation_fee_lamports), "check_admin_exempt")]
+            ),
+            (
+                &program_test.get_set_creation_fee_instruction(program_test.payer.pubkey(), 0, program_test.payer.pubkey()),
+                &[Check::err(ProgramError::Custom(AnchorErrorCode::ConstraintHasOne.into()))]
+            ),
+            (
+                &program_test.get_set_creation_fee_instruction(admin, new_creation_fee_lamports, new_treasury),
+                &[Check::success(), Check::custom(move |accounts: &[(Pubkey, Account)]| {
+                    accounts
+                        .iter()
+                        .find(|(key, _)| config.eq(key))
+                        .and_then(|(_, account)| Config::try_deserialize(&mut &account.data[..]).ok())
+                        .is_some_and(|config| config.creation_fee_lamports == new_creation_fee_lamports &&
+                            config.treasury == new_treasury
+                        )
+                }, "check_creation_fee_updated")]
+            ),
+            (
+                &later_test.get_create_mint_instruction(),
+                &[Check::success(), Check::custom(check_treasury(new_treasury, new_creation_fee_lamports), "check_new_creation_fee")]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn wrapper_authority_transfer_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    // The payer creates the wrapper and proposes the admin as the new authority
+    let old_authority = program_test.payer.pubkey();
+    let new_authority = program_test.admin.pubkey();
+    let source_mint_exists = program_test.source_mint_exists;
+
+    let check_authority = move |authority: Pubkey, pending_authority: Option<Pubkey>| {
+        move |accounts: &[(Pubkey, Account)]| {
+            find_source_mint(accounts, source_mint_exists).is_some_and(|source_mint_account|
+                source_mint_account.authority == authority &&
+                    source_mint_account.pending_authority == pending_authority
+            )
+        }
+    };
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction(), &[Check::success()]),
+            (
+                &program_test.get_transfer_wrapper_authority_instruction(old_authority, new_authority),
+                &[Check::success(), Check::custom(check_authority(old_authority, Some(new_authority)), "check_authority_proposed")]
+            ),
+            // The proposed authority has no rights until it accepts
+            (
+                &program_test.get_set_guardian_instruction(new_authority, new_authority),
+                &[Check::err(ProgramError::Custom(AnchorErrorCode::ConstraintHasOne.into()))]
+            ),
+            (
+                &program_test.get_accept_wrapper_authority_instruction(Pubkey::new_unique()),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::Unauthorized.into()))]
+            ),
+            (
+                &program_test.get_accept_wrapper_authority_instruction(new_authority),
+                &[Check::success(), Check::custom(check_authority(new_authority, None), "check_authority_accepted")]
+            ),
+            // The old authority loses its rights once the transfer is accepted
+            (
+                &program_test.get_set_guardian_instruction(old_authority, old_authority),
+                &[Check::err(ProgramError::Custom(AnchorErrorCode::ConstraintHasOne.into()))]
+            ),
+            (
+                &program_test.get_transfer_wrapper_authority_instruction(old_authority, old_authority),
+                &[Check::err(ProgramError::Custom(AnchorErrorCode::ConstraintHasOne.into()))]
+            ),
+            (&program_test.get_set_guardian_instruction(new_authority, new_authority), &[Check::success()]),
+        ]
+    );
+}
+
+#[test]
+fn migrate_wrapper_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    account_store.store_account(
+        program_test.admin.pubkey(),
+        Account::new(1_000_000_000, 0, &system_program::ID)
+    );
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction(), &[Check::success()]),
+        ]
+    );
+
+    // Rewrite the wrapper account in the legacy layout
+    let mut account_store = mollusk_context.account_store.borrow_mut();
+
+    let (_, source_mint_exists_bump) =
+        Pubkey::find_program_address(
+            &[b"mint", program_test.source_mint.pubkey().as_ref()],
+            &token_wrapper::ID,
+        );
+
+    let mut legacy_data = SourceMint::DISCRIMINATOR.to_vec();
+    legacy_data.extend_from_slice(program_test.wrapped_mint.pubkey().as_ref());
+    legacy_data.push(source_mint_exists_bump);
+
+    account_store.store_account(
+        program_test.source_mint_exists,
+        Account {
+            lamports: Rent::default().minimum_balance(legacy_data.len()),
+            data: legacy_data,
+            owner: token_wrapper::ID,
+            executable: false,
+            rent_epoch: 0
+        }
+    );
+
+    core::mem::drop(account_store);
+
+    // Legacy wrappers cannot be used until they are migrated, and can only be migrated once
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_swap_instruction(SwapType::SwapToWrapped),
+                &[Check::err(ProgramError::Custom(AnchorErrorCode::AccountDidNotDeserialize.into()))]
+            ),
+            (&program_test.get_migrate_wrapper_instruction(), &[Check::success()]),
+            (&program_test.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]),
+            (
+                &program_test.get_migrate_wrapper_instruction(),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::WrapperAlreadyMigrated.into()))]
+            ),
+        ]
+    );
+}
+
+// Upper bounds for the swaps, swap_to_wrapped includes the creation of the wrapped token account
+// of the buyer. stored_bump_compute_units_test checks that the bumps are not searched for.
+const SWAP_TO_WRAPPED_MAX_COMPUTE_UNITS: u64 = 100_000;
+const SWAP_TO_SOURCE_MAX_COMPUTE_UNITS: u64 = 60_000;
+
+#[test]
+fn swap_compute_units_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    mollusk_context.process_and_validate_instruction(
+        &program_test.get_create_mint_instruction(), &[Check::success()]
+    );
+
+    // The swaps reuse the bumps stored on the wrapper instead of searching for them
+    let swap_to_wrapped_result = mollusk_context.process_and_validate_instruction(
+        &program_test.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]
+    );
+
+    let swap_to_source_result = mollusk_context.process_and_validate_instruction(
+        &program_test.get_swap_instruction(SwapType::SwapToSource), &[Check::success()]
+    );
+
+    println!("swap_to_wrapped consumed {} compute units", swap_to_wrapped_result.compute_units_consumed);
+    println!("swap_to_source consumed {} compute units", swap_to_source_result.compute_units_consumed);
+
+    assert!(
+        swap_to_wrapped_result.compute_units_consumed <= SWAP_TO_WRAPPED_MAX_COMPUTE_UNITS,
+        "swap_to_wrapped consumed more than {} compute units", SWAP_TO_WRAPPED_MAX_COMPUTE_UNITS
+    );
+    assert!(
+        swap_to_source_result.compute_units_consumed <= SWAP_TO_SOURCE_MAX_COMPUTE_UNITS,
+        "swap_to_source consumed more than {} compute units", SWAP_TO_SOURCE_MAX_COMPUTE_UNITS
+    );
+}
+
+// The cost of every candidate address tried while searching for a bump
+const CREATE_PROGRAM_ADDRESS_COMPUTE_UNITS: u64 = 1_500;
+
+fn find_bump(seeds: &[&[u8]], program_id: &Pubkey) -> u8 {
+    Pubkey::find_program_address(seeds, program_id).1
+}
+
+// The bumps of the vault authority, the mint authority and the wrapper account, which a swap
+// takes from the wrapper account, and of the token accounts, which a swap searches for
+fn swap_bumps(wrapper: &TokenWrapperTest) -> ([u8; 3], [u8; 3]) {
+    let source_mint = wrapper.source_mint.pubkey();
+    let wrapped_mint = wrapper.wrapped_mint.pubkey();
+
+    let stored_bumps = [
+        find_bump(&[b"vault-authority", source_mint.as_ref()], &token_wrapper::ID),
+        find_bump(&[b"mint-authority", wrapped_mint.as_ref()], &token_wrapper::ID),
+        find_bump(&[b"mint", source_mint.as_ref()], &token_wrapper::ID),
+    ];
+
+    let token_account_bump = |owner: &Pubkey, mint: &Pubkey| find_bump(
+        &[owner.as_ref(), spl_token::ID.as_ref(), mint.as_ref()],
+        &mollusk_svm_programs_token::associated_token::ID
+    );
+
+    let searched_bumps = [
+        token_account_bump(&wrapper.payer.pubkey(), &source_mint),
+        token_account_bump(&wrapper.payer.pubkey(), &wrapped_mint),
+        token_account_bump(&wrapper.vault_authority, &source_mint),
+    ];
+
+    (stored_bumps, searched_bumps)
+}
+
+// Generates wrappers until the stored bumps are accepted, the searched bumps are always 255
+fn find_wrapper_with_bumps(payer: &Keypair, admin: &Keypair, treasury: Pubkey,
+            accept: impl Fn([u8; 3]) -> bool) -> TokenWrapperTest {
+    loop {
+        let wrapper = TokenWrapperTest::new_wrapper(
+            payer.insecure_clone(),
+            admin.insecure_clone(),
+            treasury,
+            Keypair::new(),
+            0
+        );
+
+        let (stored_bumps, searched_bumps) = swap_bumps(&wrapper);
+
+        if accept(stored_bumps) && searched_bumps == [u8::MAX; 3] {
+            return wrapper;
+        }
+    }
+}
+
+#[test]
+fn stored_bump_compute_units_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    // Searching would try one candidate for the first wrapper and at least three for the second,
+    // per account. With the stored bumps both wrappers cost the same.
+    let payer = Keypair::new();
+    let admin = Keypair::new();
+    let treasury = Pubkey::new_unique();
+
+    let mut first_candidate_test = find_wrapper_with_bumps(&payer, &admin, treasury, |bumps| {
+        bumps.iter().all(|bump| *bump == u8::MAX)
+    });
+    let mut later_candidate_test = find_wrapper_with_bumps(&payer, &admin, treasury, |bumps| {
+        bumps.iter().all(|bump| *bump <= u8::MAX - 2)
+    });
+
+    first_candidate_test.setup_default(&mut account_store);
+    later_candidate_test.setup_default(&mut account_store);
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    // The compute units of swap_to_source and of swap_to_wrapped into an existing token account
+    let measure_swaps = |wrapper: &TokenWrapperTest| -> (u64, u64) {
+        mollusk_context.process_and_validate_instruction_chain(
+            &[
+                (&wrapper.get_create_mint_instruction(), &[Check::success()]),
+                (&wrapper.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]),
+            ]
+        );
+
+        let swap_to_source_result = mollusk_context.process_and_validate_instruction(
+            &wrapper.get_swap_instruction(SwapType::SwapToSource), &[Check::success()]
+        );
+
+        let swap_to_wrapped_result = mollusk_context.process_and_validate_instruction(
+            &wrapper.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]
+        );
+
+        (swap_to_source_result.compute_units_consumed, swap_to_wrapped_result.compute_units_consumed)
+    };
+
+    let (first_swap_to_source, first_swap_to_wrapped) = measure_swaps(&first_candidate_test);
+    let (later_swap_to_source, later_swap_to_wrapped) = measure_swaps(&later_candidate_test);
+
+    println!("swap_to_source consumed {} and {} compute units", first_swap_to_source, later_swap_to_source);
+    println!("swap_to_wrapped consumed {} and {} compute units", first_swap_to_wrapped, later_swap_to_wrapped);
+
+    assert!(
+        first_swap_to_source.abs_diff(later_swap_to_source) < CREATE_PROGRAM_ADDRESS_COMPUTE_UNITS,
+        "swap_to_source searches for a bump"
+    );
+    assert!(
+        first_swap_to_wrapped.abs_diff(later_swap_to_wrapped) < CREATE_PROGRAM_ADDRESS_COMPUTE_UNITS,
+        "swap_to_wrapped searches for a bump"
+    );
+}
+
+#[test]
+fn collateral_check_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction(), &[Check::success()]),
+            (&program_test.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]),
+        ]
+    );
+
+    // Drain part of the vault behind the program's back
+    program_test.store_vault_amount(
+        &mut mollusk_context.account_store.borrow_mut(),
+        program_test.wrap_amount - 1
+    );
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_swap_instruction(SwapType::SwapToWrapped),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::UndercollateralizedVault.into()))]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn verify_reserves_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    let report = ReservesReport {
+        vault_amount: program_test.wrap_amount,
+        wrapped_supply: program_test.wrap_amount,
+        required_backing: program_test.wrap_amount,
+        fully_backed: true
+    };
+
+    let report_data = report.try_to_vec().expect("Could not serialize the report");
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction(), &[Check::success()]),
+            (&program_test.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]),
+            (&program_test.get_verify_reserves_instruction(), &[Check::success(), Check::return_data(&report_data)]),
+        ]
+    );
+}
+
+#[test]
+fn sweep_surplus_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction(), &[Check::success()]),
+            (&program_test.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]),
+        ]
+    );
+
+    // Donate tokens directly to the vault
+    let donated_amount = 500;
+
+    program_test.store_vault_amount(
+        &mut mollusk_context.account_store.borrow_mut(),
+        program_test.wrap_amount + donated_amount
+    );
+
+    let swept_amount = program_test.original_source_amount - program_test.wrap_amount + donated_amount;
+    let vault = program_test.vault;
+    let buyer_mint_ata = program_test.buyer_mint_ata;
+    let wrap_amount = program_test.wrap_amount;
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_sweep_surplus_instruction(program_test.buyer_mint_ata),
+                &[Check::success(), Check::custom(move |accounts: &[(Pubkey, Account)]| {
+                    token_amount(accounts, vault) == wrap_amount &&
+                        token_amount(accounts, buyer_mint_ata) == swept_amount
+                }, "check_surplus_swept")]
+            ),
+            // The backing collateral is never swept
+            (
+                &program_test.get_sweep_surplus_instruction(program_test.buyer_mint_ata),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::NoSurplus.into()))]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn recover_foreign_tokens_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    // Unrelated tokens sent to the vault authority
+    let foreign_mint = Pubkey::new_unique();
+    let foreign_amount = 1_000;
+
+    account_store.store_account(
+        foreign_mint,
+        create_account_for_mint(Mint {
+            mint_authority: CNone,
+            supply: foreign_amount,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: CNone
+        })
+    );
+
+    let token_account = |owner: Pubkey, amount: u64| create_account_for_token_account(TokenAccount{
+        mint: foreign_mint,
+        owner,
+        amount,
+        delegate: CNone,
+        state: spl_token::state::AccountState::Initialized,
+        is_native: CNone,
+        delegated_amount: 0,
+        close_authority: CNone
+    });
+
+    account_store.store_account(
+        get_associated_token_address(&program_test.vault_authority, &foreign_mint),
+        token_account(program_test.vault_authority, foreign_amount)
+    );
+
+    let destination = Pubkey::new_unique();
+
+    account_store.store_account(destination, token_account(program_test.admin.pubkey(), 0));
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction(), &[Check::success()]),
+            (&program_test.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]),
+            (
+                &program_test.get_recover_foreign_tokens_instruction(
+                    program_test.vault_authority, foreign_mint, destination
+                ),
+                &[Check::success(), Check::custom(move |accounts: &[(Pubkey, Account)]| {
+                    accounts
+                        .iter()
+                        .find(|(key, _)| destination.eq(key))
+                        .is_some_and(|(_, account)| TokenAccount::unpack(&account.data).
+                            is_ok_and(|token_account| token_account.amount == foreign_amount))
+                }, "check_foreign_tokens_recovered")]
+            ),
+            // The source tokens in the vault back the wrapped supply and cannot be recovered
+            (
+                &program_test.get_recover_foreign_tokens_instruction(
+                    program_test.vault_authority, program_test.source_mint.pubkey(), program_test.buyer_mint_ata
+                ),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::SourceMintNotRecoverable.into()))]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn circuit_breaker_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction(), &[Check::success()]),
+            (&program_test.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]),
+        ]
+    );
+
+    // Drain part of the vault behind the program's back
+    program_test.store_vault_amount(
+        &mut mollusk_context.account_store.borrow_mut(),
+        program_test.wrap_amount - 1
+    );
+
+    // The reserves check pauses the wrapper, which cannot resume while the shortfall remains
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_verify_reserves_instruction(), &[Check::success()]),
+            (
+                &program_test.get_swap_instruction(SwapType::SwapToSource),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::WrapperPaused.into()))]
+            ),
+            (
+                &program_test.get_resume_wrapper_instruction(),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::UndercollateralizedVault.into()))]
+            ),
+        ]
+    );
+
+    // Cover the shortfall
+    program_test.store_vault_amount(
+        &mut mollusk_context.account_store.borrow_mut(),
+        program_test.wrap_amount
+    );
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_resume_wrapper_instruction(), &[Check::success()]),
+            (&program_test.get_swap_instruction(SwapType::SwapToSource), &[Check::success()]),
+            (
+                &program_test.get_resume_wrapper_instruction(),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::WrapperNotPaused.into()))]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn close_wrapper_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    // Unwrap everything that was wrapped
+    program_test.source_amount = program_test.wrap_amount;
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    let vault = program_test.vault;
+    let source_mint_exists = program_test.source_mint_exists;
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction(), &[Check::success()]),
+            (&program_test.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]),
+            (
+                &program_test.get_close_wrapper_instruction(),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::WrapperNotEmpty.into()))]
+            ),
+            (&program_test.get_swap_instruction(SwapType::SwapToSource), &[Check::success()]),
+            (
+                &program_test.get_close_wrapper_instruction(),
+                &[Check::success(), Check::custom(move |accounts: &[(Pubkey, Account)]| {
+                    accounts
+                        .iter()
+                        .filter(|(key, _)| vault.eq(key) || source_mint_exists.eq(key))
+                        .all(|(_, account)| account.lamports == 0)
+                }, "check_wrapper_closed")]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn migrate_holder_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    // Move everything that was wrapped to a Token-2022 successor
+    program_test.source_amount = program_test.wrap_amount;
+
+    let mut successor_test = program_test.successor();
+    successor_test.use_token_2022_wrapped_mint();
+
+    // The successor delays unwraps, which its own successor cannot be used to skip
+    let delayed_args = CreateMintArgs {
+        unwrap_delay: 3600,
+        generation: successor_test.generation,
+        ..CreateMintArgs::default()
+    };
+
+    let next_successor_test = successor_test.successor();
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    let old_vault = program_test.vault;
+    let new_vault = successor_test.vault;
+    let new_wrapped_mint_ata = successor_test.buyer_wrapped_mint_ata;
+    let wrap_amount = program_test.wrap_amount;
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction(), &[Check::success()]),
+            (&successor_test.get_create_mint_instruction_with_args(delayed_args), &[Check::success()]),
+            (&next_successor_test.get_create_mint_instruction(), &[Check::success()]),
+            (&program_test.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]),
+            // No successor has been linked yet
+            (
+                &program_test.get_migrate_holder_instruction(&successor_test),
+                &[Check::err(ProgramError::Custom(AnchorErrorCode::AccountNotInitialized.into()))]
+            ),
+            (&program_test.get_set_successor_instruction(&successor_test), &[Check::success()]),
+            (
+                &program_test.get_migrate_holder_instruction(&successor_test),
+                &[Check::success(), Check::custom(move |accounts: &[(Pubkey, Account)]| {
+                    token_amount(accounts, old_vault) == 0 &&
+                        token_amount(accounts, new_vault) == wrap_amount &&
+                        token_amount(accounts, new_wrapped_mint_ata) == wrap_amount
+                }, "check_holder_migrated")]
+            ),
+            (&successor_test.get_set_successor_instruction(&next_successor_test), &[Check::success()]),
+            (
+                &successor_test.get_migrate_holder_instruction(&next_successor_test),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::DelayedUnwrapRequired.into()))]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn convert_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    let other_test = program_test.successor();
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    let from_vault = program_test.vault;
+    let to_vault = other_test.vault;
+    let from_wrapped_mint_ata = program_test.buyer_wrapped_mint_ata;
+    let to_wrapped_mint_ata = other_test.buyer_wrapped_mint_ata;
+    let wrap_amount = program_test.wrap_amount;
+    let convert_amount = program_test.source_amount;
+
+    // A delayed wrapper only releases its backing through request_unwrap and claim_unwrap
+    let delayed_args = CreateMintArgs {
+        unwrap_delay: 3600,
+        generation: other_test.generation,
+        ..CreateMintArgs::default()
+    };
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction(), &[Check::success()]),
+            (&other_test.get_create_mint_instruction_with_args(delayed_args), &[Check::success()]),
+            (&program_test.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]),
+            (
+                &program_test.get_convert_instruction(&other_test),
+                &[Check::success(), Check::custom(move |accounts: &[(Pubkey, Account)]| {
+                    token_amount(accounts, from_vault) == wrap_amount - convert_amount &&
+                        token_amount(accounts, to_vault) == convert_amount &&
+                        token_amount(accounts, from_wrapped_mint_ata) == wrap_amount - convert_amount &&
+                        token_amount(accounts, to_wrapped_mint_ata) == convert_amount
+                }, "check_converted")]
+            ),
+            (
+                &other_test.get_convert_instruction(&program_test),
+                &[Check::err(ProgramError::Custom(TokenWrapperError::DelayedUnwrapRequired.into()))]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn batch_swap_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    // A second wrapper of another source mint for the same buyer, wrapped into Token-2022
+    let mut other_test = TokenWrapperTest::new_wrapper(
+        program_test.payer.insecure_clone(),
+        program_test.admin.insecure_clone(),
+        program_test.treasury,
+        Keypair::new(),
+        0
+    );
+    other_test.use_token_2022_wrapped_mint();
+    other_test.setup_default(&mut account_store);
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    let buyer_wrapped_mint_atas = [program_test.buyer_wrapped_mint_ata, other_test.buyer_wrapped_mint_ata];
+    let wrapped_amount = 2 * program_test.wrap_amount - program_test.source_amount;
+
+    // The single swaps create the wrapped token accounts of the buyer
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction(), &[Check::success()]),
+            (&other_test.get_create_mint_instruction(), &[Check::success()]),
+            (&program_test.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]),
+            (&other_test.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]),
+        ]
+    );
+
+    let batch_swap_to_source =
+        TokenWrapperTest::get_batch_swap_instruction(&[&program_test, &other_test], SwapType::SwapToSource);
+
+    let mut mismatched_batch = batch_swap_to_source.clone();
+    mismatched_batch.accounts.truncate(2 + 8 + 4);
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &TokenWrapperTest::get_batch_swap_instruction(&[&program_test, &other_test], SwapType::SwapToWrapped),
+                &[Check::success()]
+            ),
+            (
+                &batch_swap_to_source,
+                &[Check::success(), Check::custom(move |accounts: &[(Pubkey, Account)]| {
+                    buyer_wrapped_mint_atas.iter().all(|buyer_wrapped_mint_ata| accounts
+                        .iter()
+                        .find(|(key, _)| buyer_wrapped_mint_ata.eq(key))
+                        .is_some_and(|(_, account)| TokenAccount::unpack(&account.data[..TokenAccount::LEN]).
+                            is_ok_and(|token_account| token_account.amount == wrapped_amount)))
+                }, "check_batch_swapped")]
+            ),
+            (
+                &mismatched_batch,
+                &[Check::err(ProgramError::Custom(TokenWrapperError::InvalidBatchAccounts.into()))]
+            ),
+        ]
+    );
+}
+
+// The number of wrappers a single transaction can batch, see `batch_swap_to_wrapped`
+const MAX_BATCH_SIZE: usize = 8;
+
+// The compute unit limit of a transaction
+const MAX_TRANSACTION_COMPUTE_UNITS: u64 = 1_400_000;
+
+#[test]
+fn batch_swap_compute_units_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    // The largest batch that fits in a transaction, every wrapper has its own source mint
+    let payer = Keypair::new();
+    let admin = Keypair::new();
+    let treasury = Pubkey::new_unique();
+
+    let wrappers: Vec<TokenWrapperTest> = (0..MAX_BATCH_SIZE).map(|_| {
+        let mut wrapper = TokenWrapperTest::new_wrapper(
+            payer.insecure_clone(),
+            admin.insecure_clone(),
+            treasury,
+            Keypair::new(),
+            0
+        );
+        wrapper.setup_default(&mut account_store);
+        wrapper
+    }).collect();
+
+    let wrapper_refs: Vec<&TokenWrapperTest> = wrappers.iter().collect();
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    // The single swaps create the wrapped token accounts of the buyer
+    for wrapper in &wrappers {
+        mollusk_context.process_and_validate_instruction_chain(
+            &[
+                (&wrapper.get_create_mint_instruction(), &[Check::success()]),
+                (&wrapper.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]),
+            ]
+        );
+    }
+
+    let batch_swap_to_wrapped = TokenWrapperTest::get_batch_swap_instruction(&wrapper_refs, SwapType::SwapToWrapped);
+    let batch_swap_to_source = TokenWrapperTest::get_batch_swap_instruction(&wrapper_refs, SwapType::SwapToSource);
+
+    let batch_swap_to_wrapped_result = mollusk_context.process_and_validate_instruction(
+        &batch_swap_to_wrapped, &[Check::success()]
+    );
+
+    let batch_swap_to_source_result = mollusk_context.process_and_validate_instruction(
+        &batch_swap_to_source, &[Check::success()]
+    );
+
+    println!(
+        "batch_swap_to_wrapped of {} wrappers consumed {} compute units",
+        MAX_BATCH_SIZE, batch_swap_to_wrapped_result.compute_units_consumed
+    );
+    println!(
+        "batch_swap_to_source of {} wrappers consumed {} compute units",
+        MAX_BATCH_SIZE, batch_swap_to_source_result.compute_units_consumed
+    );
+
+    assert!(
+        batch_swap_to_wrapped_result.compute_units_consumed <= MAX_TRANSACTION_COMPUTE_UNITS,
+        "batch_swap_to_wrapped does not fit in a transaction"
+    );
+    assert!(
+        batch_swap_to_source_result.compute_units_consumed <= MAX_TRANSACTION_COMPUTE_UNITS,
+        "batch_swap_to_source does not fit in a transaction"
+    );
+}
+
+#[test]
+fn swap_all_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    // Wrap the whole balance and unwrap all of it again
+    program_test.wrap_amount = SWAP_ALL;
+    program_test.source_amount = SWAP_ALL;
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    let buyer_mint_ata = program_test.buyer_mint_ata;
+    let buyer_wrapped_mint_ata = program_test.buyer_wrapped_mint_ata;
+    let original_source_amount = program_test.original_source_amount;
+
+    let check_balances = move |source_amount: u64, wrapped_amount: u64| {
+        move |accounts: &[(Pubkey, Account)]| {
+            token_amount(accounts, buyer_mint_ata) == source_amount &&
+                token_amount(accounts, buyer_wrapped_mint_ata) == wrapped_amount
+        }
+    };
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction(), &[Check::success()]),
+            (
+                &program_test.get_swap_instruction(SwapType::SwapToWrapped),
+                &[Check::success(), Check::custom(check_balances(0, original_source_amount), "check_wrapped_all")]
+            ),
+            (
+                &program_test.get_swap_instruction(SwapType::SwapToSource),
+                &[Check::success(), Check::custom(check_balances(original_source_amount, 0), "check_unwrapped_all")]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn wrapper_metadata_test() {
+
+    // Size of the version 1 layout, later fields are carved out of the reserved space
+    assert_eq!(SourceMint::DISCRIMINATOR.len() + SourceMint::INIT_SPACE, 366);
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+    program_test.use_token_2022_wrapped_mint();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    let clock = mollusk.sysvars.clock.clone();
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    let (_, vault_authority_bump) =
+        Pubkey::find_program_address(
+            &[b"vault-authority", program_test.source_mint.pubkey().as_ref()],
+            &token_wrapper::ID,
+        );
+    let (_, mint_authority_bump) =
+        Pubkey::find_program_address(
+            &[b"mint-authority", program_test.wrapped_mint.pubkey().as_ref()],
+            &token_wrapper::ID,
+        );
+
+    let source_mint = program_test.source_mint.pubkey();
+    let wrapped_mint = program_test.wrapped_mint.pubkey();
+    let vault = program_test.vault;
+    let source_mint_exists = program_test.source_mint_exists;
+    let wrapped_token_program = program_test.wrapped_token_program;
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (
+                &program_test.get_create_mint_instruction(),
+                &[Check::success(), Check::custom(move |accounts: &[(Pubkey, Account)]| {
+                    find_source_mint(accounts, source_mint_exists).is_some_and(|source_mint_account|
+                        source_mint_account.version == SourceMint::CURRENT_VERSION &&
+                            source_mint_account.source_mint == source_mint &&
+                            source_mint_account.wrapped_mint == wrapped_mint &&
+                            source_mint_account.vault == vault &&
+                            source_mint_account.decimals == 9 &&
+                            source_mint_account.created_at_slot == clock.slot &&
+                            source_mint_account.created_at == clock.unix_timestamp &&
+                            source_mint_account.vault_authority_bump == vault_authority_bump &&
+                            source_mint_account.mint_authority_bump == mint_authority_bump &&
+                            source_mint_account.token_program == spl_token::ID &&
+                            source_mint_account.wrapped_token_program == wrapped_token_program
+                    )
+                }, "check_wrapper_metadata")]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn allowlist_hook_test() {
+
+    let mollusk = TokenWrapperTest::get_mollusk_with_programs();
+
+    let mut program_test = TokenWrapperTest::new();
+    program_test.use_token_2022_wrapped_mint();
+
+    let mut account_store = TokenWrapperAccountStore::default();
+
+    program_test.setup_default(&mut account_store);
+
+    // The admin manages the allowlist
+    account_store.store_account(
+        program_test.admin.pubkey(),
+        Account::new(1_000_000_000, 0, &system_program::ID)
+    );
+
+    let mollusk_context = mollusk.with_context(account_store);
+
+    let hooked_args = CreateMintArgs {
+        allowlist_authority: Some(program_test.admin.pubkey()),
+        ..CreateMintArgs::default()
+    };
+
+    let buyer = program_test.payer.pubkey();
+    let receiver = Pubkey::new_unique();
+    let transfer_amount = 1_000;
+
+    mollusk_context.process_and_validate_instruction_chain(
+        &[
+            (&program_test.get_create_mint_instruction_with_args(hooked_args), &[Check::success()]),
+            // Minting and burning do not go through the hook
+            (&program_test.get_swap_instruction(SwapType::SwapToWrapped), &[Check::success()]),
+            (&program_test.get_create_wrapped_ata_instruction(receiver), &[Check::success()]),
+            (
+                &program_test.get_hooked_transfer_instruction(receiver, transfer_amount),
+                &[Check::err(ProgramError::Custom(AllowlistHookError::SenderNotAllowlisted.into()))]
+            ),
+            (&program_test.get_allowlist_instruction(buyer, true), &[Check::success()]),
+            (
+                &program_test.get_hooked_transfer_instruction(receiver, transfer_amount),
+                &[Check::err(ProgramError::Custom(AllowlistHookError::ReceiverNotAllowlisted.into()))]
+            ),
+            (&program_test.get_allowlist_instruction(receiver, true), &[Check::success()]),
+            (&program_test.get_hooked_transfer_instruction(receiver, transfer_amount), &[Check::success()]),
+            (&program_test.get_allowlist_instruction(receiver, false), &[Check::success()]),
+            (
+                &program_test.get_hooked_transfer_instruction(receiver, transfer_amount),
+                &[Check::err(ProgramError::Custom(AllowlistHookError::ReceiverNotAllowlisted.into()))]
+            ),
+            (&program_test.get_swap_instruction(SwapType::SwapToSource), &[Check::success()]),
+        ]
+    );
+}
+
+pub struct TokenWrapperAccountStore{
+    pub accounts_map:HashMap<Pubkey, Account>
+}
+
+impl TokenWrapperAccountStore{
+    pub fn default()->Self{
+        TokenWrapperAccountStore{ 
+            accounts_map: HashMap::<Pubkey, Account>::default() 
+        }
+    }
+
+    pub fn get_account_or_default(&self, account_key: &Pubkey)->Account{
+        self.get_account(account_key).
+            unwrap_or(self.default_account(&Pubkey::default()))
+    }
+}
+
+impl AccountStore for TokenWrapperAccountStore{
+
+    fn get_account(&self, account_key: &Pubkey) -> Option<Account>{
+        self.accounts_map.get(account_key).cloned()
+    }
+
+    #[inline(always)]
+    fn store_account(&mut self, account_key: Pubkey, account: Account){
+        self.accounts_map.insert(account_key, account);
+    }
+}
+
+pub enum SwapType{
+    SwapToWrapped,
+    SwapToSource
+}
+
+pub struct TokenWrapperTest {
+
+    // Signers
+    pub payer: Keypair,        
+    pub admin: Keypair,
+
+    // Fee recipient
+    pub treasury: Pubkey,
+
+    // Mint accounts
+    pub source_mint: Keypair,  
+    pub wrapped_mint: Keypair, 
+
+    // PDAs
+    pub mint_authority: Pubkey,
+    pub freeze_authority: Pubkey,
+    pub vault_authority: Pubkey,        
+    pub source_mint_exists: Pubkey,     
+    pub config: Pubkey,
+    pub config_bump: u8,
+
+    // Token accounts
+    pub vault: Pubkey,                  
+    pub buyer_mint_ata: Pubkey,         
+    pub buyer_wrapped_mint_ata: Pubkey, 
+
+    // Programs
+    pub wrapped_token_program: Pubkey,
+
+    // Wrapper generation of the source mint
+    pub generation: u8,
+
+    // Data 
+    pub original_source_amount:u64,
+    pub original_wrapped_amount:u64,
+    pub wrap_amount:u64,
+    pub source_amount:u64,
+}
+
+impl TokenWrapperTest {
+
+     pub fn new() -> Self {
+        Self::new_wrapper(Keypair::new(), Keypair::new(), Pubkey::new_unique(), Keypair::new(), 0)
+    }
+
+    /// Next generation wrapper of the same source mint, with the same payer and admin.
+    pub fn successor(&self) -> Self {
+        let mut successor = Self::new_wrapper(
+            self.payer.insecure_clone(),
+            self.admin.insecure_clone(),
+            self.treasury,
+            self.source_mint.insecure_clone(),
+            self.generation + 1
+        );
+
+        successor.original_source_amount = self.original_source_amount;
+        successor.wrap_amount = self.wrap_amount;
+        successor.source_amount = self.source_amount;
+
+        successor
+    }
+
+    fn new_wrapper(payer: Keypair, admin: Keypair, treasury: Pubkey, source_mint: Keypair, generation: u8) -> Self {
+        let wrapped_mint = Keypair::new();
+
+        let generation_seed: &[u8] = if generation == 0 { &[] } else { &[generation] };
+
+        let (mint_authority, _) =
+            Pubkey::find_program_address(
+                &[b"mint-authority", wrapped_mint.pubkey().as_ref()],
+                &token_wrapper::ID,
+            );
+        let (freeze_authority, _) =
+            Pubkey::find_program_address(
+                &[b"freeze-authority", wrapped_mint.pubkey().as_ref()],
+                &token_wrapper::ID,
+            );
+        let (vault_authority, _) =
+            Pubkey::find_program_address(
+                &[b"vault-authority", source_mint.pubkey().as_ref(), generation_seed],
+                &token_wrapper::ID,
+            );
+        let (source_mint_exists, _) =
+            Pubkey::find_program_address(
+                &[b"mint", source_mint.pubkey().as_ref(), generation_seed],
+                &token_wrapper::ID,
+            );
+        let (config, config_bump) =
+            Pubkey::find_program_address(
+                &[b"config"],
+                &token_wrapper::ID,
+            );
+
+        let vault = get_associated_token_address(&vault_authority, &source_mint.pubkey());
+        let buyer_mint_ata = get_associated_token_address(&payer.pubkey(), &source_mint.pubkey());
+        let buyer_wrapped_mint_ata =
+            get_associated_token_address(&payer.pubkey(), &wrapped_mint.pubkey());
+
+        Self {
+            payer,
+            admin,
+
+            treasury,
+
+            source_mint,
+            wrapped_mint,
+
+            mint_authority,
+            freeze_authority,
+            vault_authority,
+            source_mint_exists,
+            config,
+            config_bump,
+
+            vault,
+            buyer_mint_ata,
+            buyer_wrapped_mint_ata,
+
+            wrapped_token_program: spl_token::ID,
+
+            generation,
+
+            original_source_amount:0,
+            original_wrapped_amount:0,
+
+            wrap_amount:0,
+            source_amount:0
+        }
+    }
+
+    #[inline(always)]
+    pub fn _generate_keys(&mut self){
+        *self = TokenWrapperTest::new();
+    }
+
+    pub fn use_token_2022_wrapped_mint(&mut self){
+        self.wrapped_token_program = mollusk_svm_programs_token::token2022::ID;
+        self.buyer_wrapped_mint_ata = get_associated_token_address_with_program_id(
+            &self.payer.pubkey(), &self.wrapped_mint.pubkey(), &self.wrapped_token_program);
+    }
+
+    pub fn get_mollusk_with_programs() -> Mollusk{
+        let mut runtime = Mollusk::new(&token_wrapper::ID, "token_wrapper");
+
+        mollusk_svm_programs_token::token::add_program(&mut runtime);
+
+        mollusk_svm_programs_token::token2022::add_program(&mut runtime);
+        
+        mollusk_svm_programs_token::associated_token::add_program(&mut runtime);
+
+        runtime.add_program(&allowlist_hook::ID, "allowlist_hook", &mollusk_svm::program::loader_keys::LOADER_V3);
+
+        runtime
+    }
+        
+    pub fn setup(&mut self, accounts: &mut TokenWrapperAccountStore, buyer_lamports: u64,
+                mint_supply: u64, token_account_balance: u64, wrap_amount:u64, source_amount:u64){
+        // Add the payer account
+        let rent = Rent::default();
+
+        let buyer_minimum_balance = rent.minimum_balance(0);
+        let buyer_total_lamports = buyer_lamports.max(buyer_minimum_balance);
+        
+        accounts.store_account(
+            self.payer.pubkey(), 
+            Account::new(buyer_total_lamports, 0, &system_program::ID)
+        );
+
+        // Add the source mint account
+        let mint_data = Mint {
+            mint_authority: CNone,
+            supply: mint_supply,
+            decimals: 9,
+            is_initialized: true,
+            freeze_authority: CNone
+        };
+
+        accounts.store_account(
+            self.source_mint.pubkey(), 
+            create_account_for_mint(mint_data)
+        );
+
+        // Add the user token account
+        let token_account_data = TokenAccount{
+            mint: self.source_mint.pubkey(),
+            owner: self.payer.pubkey(),
+            amount: token_account_balance,
+            delegate: CNone,
+            state: spl_token::state::AccountState::Initialized,
+            is_native: CNone,
+            delegated_amount: 0,
+            close_authority: CNone
+        };
+
+        accounts.store_account(
+            self.buyer_mint_ata, 
+            create_account_for_token_account(token_account_data)
+        );
+
+        // Add the program config, wrapper creation is open by default
+        self.store_config(accounts, CreationPolicy::Open);
+
+        // Add the programs
+        let token_key_account_pair = 
+            mollusk_svm_programs_token::token::keyed_account();
+        let token_2022_key_account_pair = 
+            mollusk_svm_programs_token::token2022::keyed_account();
+        let associated_token_key_account_pair = 
+            mollusk_svm_programs_token::associated_token::keyed_account();
+        let system_program_key_account_pair = 
+            keyed_account_for_system_program();
+        
+        accounts.store_account(
+            token_key_account_pair.0,
+            token_key_account_pair.1
+        );
+
+        accounts.store_account(
+            token_2022_key_account_pair.0,
+            token_2022_key_account_pair.1
+        );
+
+        accounts.store_account(
+            associated_token_key_account_pair.0, 
+            associated_token_key_account_pair.1
+        );
+
+        accounts.store_account(
             system_program_key_account_pair.0, 
             system_program_key_account_pair.1
         );
 
+        accounts.store_account(
+            allowlist_hook::ID,
+            mollusk_svm::program::create_program_account_loader_v3(&allowlist_hook::ID)
+        );
+
         // Set the original balances
         self.original_source_amount = token_account_balance;
         self.original_wrapped_amount = 0;
@@ -432,6 +2395,35 @@ impl TokenWrapperTest {
 
     }
 
+    pub fn store_config(&self, accounts: &mut TokenWrapperAccountStore, creation_policy: CreationPolicy){
+        self.store_config_with_fee(accounts, creation_policy, 0);
+    }
+
+    pub fn store_config_with_fee(&self, accounts: &mut TokenWrapperAccountStore,
+                creation_policy: CreationPolicy, creation_fee_lamports: u64){
+        let config = Config {
+            admin: self.admin.pubkey(),
+            creation_policy,
+            creation_fee_lamports,
+            treasury: self.treasury,
+            bump: self.config_bump
+        };
+
+        let mut config_data = Vec::new();
+        config.try_serialize(&mut config_data).expect("Could not serialize the config");
+
+        accounts.store_account(
+            self.config,
+            Account {
+                lamports: Rent::default().minimum_balance(config_data.len()),
+                data: config_data,
+                owner: token_wrapper::ID,
+                executable: false,
+                rent_epoch: 0
+            }
+        );
+    }
+
     #[inline(always)]
     pub fn setup_default(&mut self, validator: &mut TokenWrapperAccountStore){
         // This is the default test and is expected to pass
@@ -445,56 +2437,201 @@ impl TokenWrapperTest {
         );
     }
 
-    #[inline(always)]
-    pub fn setup_fail_1(&mut self, validator: &mut TokenWrapperAccountStore){
-        // This is a failing test and it should fail because the amount the user wants to deposit
-        // is larger than their balance
-        self.setup(
-            validator,
-            1_000_000_000_000_000_000,     
-            1_000_000_000_000_000,  
-            1_000_000,
-            100_000_000,
-            10_000
-        );
+    #[inline(always)]
+    pub fn setup_fail_1(&mut self, validator: &mut TokenWrapperAccountStore){
+        // This is a failing test and it should fail because the amount the user wants to deposit
+        // is larger than their balance
+        self.setup(
+            validator,
+            1_000_000_000_000_000_000,     
+            1_000_000_000_000_000,  
+            1_000_000,
+            100_000_000,
+            10_000
+        );
+    }
+
+    #[inline(always)]
+    pub fn setup_fail_2(&mut self, validator: &mut TokenWrapperAccountStore){
+        // This is a failing test and it should fail because the amount the user wants to swap back
+        // to the source is larger than their balance for the wrapped
+        self.setup(
+            validator,
+            1_000_000_000_000_000_000,     
+            1_000_000_000_000_000,  
+            1_000_000,
+            100_000,
+            100_001
+        );
+    }
+
+    #[inline(always)]
+    pub fn get_create_mint_instruction(&self)->Instruction{
+        self.get_create_mint_instruction_with_args(CreateMintArgs{
+            generation: self.generation,
+            ..CreateMintArgs::default()
+        })
+    }
+
+    pub fn get_create_mint_instruction_with_args(&self, args:CreateMintArgs)->Instruction{
+        let mut create_mint_accounts = Vec::<AccountMeta>::with_capacity(19);
+        create_mint_accounts.push(AccountMeta::new(self.payer.pubkey(), true));
+        create_mint_accounts.push(AccountMeta::new_readonly(self.mint_authority, false));
+        create_mint_accounts.push(AccountMeta::new_readonly(self.freeze_authority, false));
+        create_mint_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        create_mint_accounts.push(AccountMeta::new(self.wrapped_mint.pubkey(), true));
+        create_mint_accounts.push(AccountMeta::new_readonly(self.vault_authority, false));
+        create_mint_accounts.push(AccountMeta::new(self.vault, false));
+        create_mint_accounts.push(AccountMeta::new(self.source_mint_exists, false));
+        create_mint_accounts.push(AccountMeta::new_readonly(self.config, false));
+        // No allowed source mint entry
+        create_mint_accounts.push(AccountMeta::new_readonly(token_wrapper::ID, false));
+        create_mint_accounts.push(AccountMeta::new(self.treasury, false));
+        create_mint_accounts.push(AccountMeta::new_readonly(system_program::ID, false));
+        create_mint_accounts.push(AccountMeta::new_readonly(spl_token::ID, false));
+        create_mint_accounts.push(AccountMeta::new_readonly(self.wrapped_token_program, false));
+        create_mint_accounts.push(AccountMeta::new_readonly(mollusk_svm_programs_token::associated_token::ID, false));
+
+        if args.allowlist_authority.is_some() {
+            let (extra_account_meta_list, allowlist_config) = self.get_allowlist_accounts();
+
+            create_mint_accounts.push(AccountMeta::new_readonly(allowlist_hook::ID, false));
+            create_mint_accounts.push(AccountMeta::new(extra_account_meta_list, false));
+            create_mint_accounts.push(AccountMeta::new(allowlist_config, false));
+        } else {
+            // No allowlist hook
+            create_mint_accounts.push(AccountMeta::new_readonly(token_wrapper::ID, false));
+            create_mint_accounts.push(AccountMeta::new_readonly(token_wrapper::ID, false));
+            create_mint_accounts.push(AccountMeta::new_readonly(token_wrapper::ID, false));
+        }
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: create_mint_accounts.clone(),
+            data: CreateMint{ args }.data()
+        }
+    }
+
+    pub fn store_program_data(&self, accounts: &mut TokenWrapperAccountStore, upgrade_authority: Pubkey){
+        // Program data header of the upgradeable loader, the state tag, the deployment slot and the
+        // upgrade authority. The program itself is loaded by mollusk.
+        let mut program_data = Vec::with_capacity(45);
+        program_data.extend_from_slice(&3u32.to_le_bytes());
+        program_data.extend_from_slice(&0u64.to_le_bytes());
+        program_data.push(1);
+        program_data.extend_from_slice(upgrade_authority.as_ref());
+
+        accounts.store_account(
+            token_wrapper::ID,
+            mollusk_svm::program::create_program_account_loader_v3(&token_wrapper::ID)
+        );
+
+        accounts.store_account(
+            self.get_program_data(),
+            Account {
+                lamports: Rent::default().minimum_balance(program_data.len()),
+                data: program_data,
+                owner: mollusk_svm::program::loader_keys::LOADER_V3,
+                executable: false,
+                rent_epoch: 0
+            }
+        );
+    }
+
+    pub fn get_program_data(&self)->Pubkey{
+        let (program_data, _) =
+            Pubkey::find_program_address(
+                &[token_wrapper::ID.as_ref()],
+                &mollusk_svm::program::loader_keys::LOADER_V3,
+            );
+        program_data
+    }
+
+    pub fn get_initialize_config_instruction(&self, upgrade_authority:Pubkey, creation_policy:CreationPolicy)->Instruction{
+        let mut initialize_config_accounts = Vec::<AccountMeta>::with_capacity(5);
+
+        initialize_config_accounts.push(AccountMeta::new(upgrade_authority, true));
+        initialize_config_accounts.push(AccountMeta::new(self.config, false));
+        initialize_config_accounts.push(AccountMeta::new_readonly(token_wrapper::ID, false));
+        initialize_config_accounts.push(AccountMeta::new_readonly(self.get_program_data(), false));
+        initialize_config_accounts.push(AccountMeta::new_readonly(system_program::ID, false));
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: initialize_config_accounts,
+            data: InitializeConfig{
+                admin: self.admin.pubkey(),
+                creation_policy,
+                creation_fee_lamports: 0,
+                treasury: self.treasury
+            }.data()
+        }
+    }
+
+    pub fn get_set_creation_policy_instruction(&self, admin:Pubkey, creation_policy:CreationPolicy)->Instruction{
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: vec![
+                AccountMeta::new_readonly(admin, true),
+                AccountMeta::new(self.config, false),
+            ],
+            data: SetCreationPolicy{ creation_policy }.data()
+        }
+    }
+
+    pub fn get_set_creation_fee_instruction(&self, admin:Pubkey, creation_fee_lamports:u64, treasury:Pubkey)->Instruction{
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: vec![
+                AccountMeta::new_readonly(admin, true),
+                AccountMeta::new(self.config, false),
+            ],
+            data: SetCreationFee{ creation_fee_lamports, treasury }.data()
+        }
     }
 
-    #[inline(always)]
-    pub fn setup_fail_2(&mut self, validator: &mut TokenWrapperAccountStore){
-        // This is a failing test and it should fail because the amount the user wants to swap back
-        // to the source is larger than their balance for the wrapped
-        self.setup(
-            validator,
-            1_000_000_000_000_000_000,     
-            1_000_000_000_000_000,  
-            1_000_000,
-            100_000,
-            100_001
-        );
+    pub fn get_allowed_source_mint(&self)->Pubkey{
+        let (allowed_source_mint, _) =
+            Pubkey::find_program_address(
+                &[b"allowed-source", self.source_mint.pubkey().as_ref()],
+                &token_wrapper::ID,
+            );
+        allowed_source_mint
     }
 
-    pub fn get_create_mint_instruction(&self)->Instruction{
-        let mut create_mint_accounts = Vec::<AccountMeta>::with_capacity(11);
-        create_mint_accounts.push(AccountMeta::new(self.payer.pubkey(), true));
-        create_mint_accounts.push(AccountMeta::new_readonly(self.mint_authority, false));
-        create_mint_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
-        create_mint_accounts.push(AccountMeta::new(self.wrapped_mint.pubkey(), true));
-        create_mint_accounts.push(AccountMeta::new_readonly(self.vault_authority, false));
-        create_mint_accounts.push(AccountMeta::new(self.vault, false));
-        create_mint_accounts.push(AccountMeta::new(self.source_mint_exists, false));
-        create_mint_accounts.push(AccountMeta::new_readonly(system_program::ID, false));
-        create_mint_accounts.push(AccountMeta::new_readonly(spl_token::ID, false));
-        create_mint_accounts.push(AccountMeta::new_readonly(mollusk_svm_programs_token::associated_token::ID, false));
+    pub fn get_allow_source_mint_instruction(&self, admin:Pubkey, allow:bool)->Instruction{
+        let mut allow_source_mint_accounts = Vec::<AccountMeta>::with_capacity(5);
+
+        allow_source_mint_accounts.push(AccountMeta::new(admin, true));
+        allow_source_mint_accounts.push(AccountMeta::new_readonly(self.config, false));
+        allow_source_mint_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        allow_source_mint_accounts.push(AccountMeta::new(self.get_allowed_source_mint(), false));
+
+        let data = if allow {
+            allow_source_mint_accounts.push(AccountMeta::new_readonly(system_program::ID, false));
+            AllowSourceMint{}.data()
+        } else {
+            DisallowSourceMint{}.data()
+        };
 
         Instruction {
             program_id: token_wrapper::ID,
-            accounts: create_mint_accounts.clone(),
-            data: CreateMint{}.data()
+            accounts: allow_source_mint_accounts,
+            data
         }
     }
 
+    pub fn get_create_mint_instruction_with_allowed_source(&self)->Instruction{
+        let mut instruction = self.get_create_mint_instruction();
+
+        // Pass the allowed source mint entry in place of the missing one
+        instruction.accounts[9] = AccountMeta::new_readonly(self.get_allowed_source_mint(), false);
+
+        instruction
+    }
+
     pub fn get_swap_instruction(&self, swap:SwapType)->Instruction{
-        let mut swap_accounts = Vec::<AccountMeta>::with_capacity(13);
+        let mut swap_accounts = Vec::<AccountMeta>::with_capacity(14);
 
         swap_accounts.push(AccountMeta::new(self.payer.pubkey(), true));
         swap_accounts.push(AccountMeta::new(self.buyer_mint_ata, false));
@@ -507,6 +2644,7 @@ impl TokenWrapperTest {
         swap_accounts.push(AccountMeta::new_readonly(self.source_mint_exists, false));
         swap_accounts.push(AccountMeta::new_readonly(system_program::ID, false));
         swap_accounts.push(AccountMeta::new_readonly(spl_token::ID, false));
+        swap_accounts.push(AccountMeta::new_readonly(self.wrapped_token_program, false));
         swap_accounts.push(AccountMeta::new_readonly(mollusk_svm_programs_token::associated_token::ID, false));
 
         let data = match swap {
@@ -525,6 +2663,530 @@ impl TokenWrapperTest {
         }
     }
 
+    pub fn get_migrate_wrapper_instruction(&self)->Instruction{
+        let mut migrate_wrapper_accounts = Vec::<AccountMeta>::with_capacity(8);
+
+        migrate_wrapper_accounts.push(AccountMeta::new(self.admin.pubkey(), true));
+        migrate_wrapper_accounts.push(AccountMeta::new_readonly(self.config, false));
+        migrate_wrapper_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        migrate_wrapper_accounts.push(AccountMeta::new_readonly(self.vault_authority, false));
+        migrate_wrapper_accounts.push(AccountMeta::new_readonly(self.vault, false));
+        migrate_wrapper_accounts.push(AccountMeta::new(self.source_mint_exists, false));
+        migrate_wrapper_accounts.push(AccountMeta::new_readonly(system_program::ID, false));
+        migrate_wrapper_accounts.push(AccountMeta::new_readonly(spl_token::ID, false));
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: migrate_wrapper_accounts,
+            data: MigrateWrapper{}.data()
+        }
+    }
+
+    pub fn get_verify_reserves_instruction(&self)->Instruction{
+        let mut verify_reserves_accounts = Vec::<AccountMeta>::with_capacity(4);
+
+        verify_reserves_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        verify_reserves_accounts.push(AccountMeta::new_readonly(self.wrapped_mint.pubkey(), false));
+        verify_reserves_accounts.push(AccountMeta::new_readonly(self.vault, false));
+        verify_reserves_accounts.push(AccountMeta::new(self.source_mint_exists, false));
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: verify_reserves_accounts,
+            data: VerifyReserves{}.data()
+        }
+    }
+
+    pub fn get_sweep_surplus_instruction(&self, recipient:Pubkey)->Instruction{
+        let mut sweep_surplus_accounts = Vec::<AccountMeta>::with_capacity(8);
+
+        sweep_surplus_accounts.push(AccountMeta::new_readonly(self.payer.pubkey(), true));
+        sweep_surplus_accounts.push(AccountMeta::new_readonly(self.vault_authority, false));
+        sweep_surplus_accounts.push(AccountMeta::new(self.vault, false));
+        sweep_surplus_accounts.push(AccountMeta::new(recipient, false));
+        sweep_surplus_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        sweep_surplus_accounts.push(AccountMeta::new_readonly(self.wrapped_mint.pubkey(), false));
+        sweep_surplus_accounts.push(AccountMeta::new_readonly(self.source_mint_exists, false));
+        sweep_surplus_accounts.push(AccountMeta::new_readonly(spl_token::ID, false));
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: sweep_surplus_accounts,
+            data: SweepSurplus{}.data()
+        }
+    }
+
+    pub fn get_recover_foreign_tokens_instruction(&self, owner:Pubkey, foreign_mint:Pubkey,
+                destination:Pubkey)->Instruction{
+        let mut recover_foreign_tokens_accounts = Vec::<AccountMeta>::with_capacity(9);
+
+        recover_foreign_tokens_accounts.push(AccountMeta::new_readonly(self.admin.pubkey(), true));
+        recover_foreign_tokens_accounts.push(AccountMeta::new_readonly(self.config, false));
+        recover_foreign_tokens_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        recover_foreign_tokens_accounts.push(AccountMeta::new_readonly(self.source_mint_exists, false));
+        recover_foreign_tokens_accounts.push(AccountMeta::new_readonly(owner, false));
+        recover_foreign_tokens_accounts.push(AccountMeta::new_readonly(foreign_mint, false));
+        recover_foreign_tokens_accounts.push(AccountMeta::new(get_associated_token_address(&owner, &foreign_mint), false));
+        recover_foreign_tokens_accounts.push(AccountMeta::new(destination, false));
+        recover_foreign_tokens_accounts.push(AccountMeta::new_readonly(spl_token::ID, false));
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: recover_foreign_tokens_accounts,
+            data: RecoverForeignTokens{}.data()
+        }
+    }
+
+    pub fn get_resume_wrapper_instruction(&self)->Instruction{
+        let mut resume_wrapper_accounts = Vec::<AccountMeta>::with_capacity(5);
+
+        resume_wrapper_accounts.push(AccountMeta::new_readonly(self.payer.pubkey(), true));
+        resume_wrapper_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        resume_wrapper_accounts.push(AccountMeta::new_readonly(self.wrapped_mint.pubkey(), false));
+        resume_wrapper_accounts.push(AccountMeta::new_readonly(self.vault, false));
+        resume_wrapper_accounts.push(AccountMeta::new(self.source_mint_exists, false));
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: resume_wrapper_accounts,
+            data: ResumeWrapper{}.data()
+        }
+    }
+
+    pub fn get_update_interest_rate_instruction(&self, authority:Pubkey, rate:i16)->Instruction{
+        let mut update_rate_accounts = Vec::<AccountMeta>::with_capacity(6);
+
+        update_rate_accounts.push(AccountMeta::new_readonly(authority, true));
+        update_rate_accounts.push(AccountMeta::new_readonly(self.mint_authority, false));
+        update_rate_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        update_rate_accounts.push(AccountMeta::new(self.wrapped_mint.pubkey(), false));
+        update_rate_accounts.push(AccountMeta::new_readonly(self.source_mint_exists, false));
+        update_rate_accounts.push(AccountMeta::new_readonly(self.wrapped_token_program, false));
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: update_rate_accounts,
+            data: UpdateInterestRate{ rate }.data()
+        }
+    }
+
+    pub fn get_compliance_instruction(&self, compliance_authority:Pubkey, freeze:bool)->Instruction{
+        let mut compliance_accounts = Vec::<AccountMeta>::with_capacity(7);
+
+        compliance_accounts.push(AccountMeta::new_readonly(compliance_authority, true));
+        compliance_accounts.push(AccountMeta::new_readonly(self.freeze_authority, false));
+        compliance_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        compliance_accounts.push(AccountMeta::new_readonly(self.wrapped_mint.pubkey(), false));
+        compliance_accounts.push(AccountMeta::new_readonly(self.source_mint_exists, false));
+        compliance_accounts.push(AccountMeta::new(self.buyer_wrapped_mint_ata, false));
+        compliance_accounts.push(AccountMeta::new_readonly(self.wrapped_token_program, false));
+
+        let data = if freeze {
+            FreezeHolder{}.data()
+        } else {
+            ThawHolder{}.data()
+        };
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: compliance_accounts,
+            data
+        }
+    }
+
+    pub fn get_transfer_wrapper_authority_instruction(&self, authority:Pubkey, new_authority:Pubkey)->Instruction{
+        let mut transfer_authority_accounts = Vec::<AccountMeta>::with_capacity(3);
+
+        transfer_authority_accounts.push(AccountMeta::new_readonly(authority, true));
+        transfer_authority_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        transfer_authority_accounts.push(AccountMeta::new(self.source_mint_exists, false));
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: transfer_authority_accounts,
+            data: TransferWrapperAuthority{ new_authority }.data()
+        }
+    }
+
+    pub fn get_accept_wrapper_authority_instruction(&self, pending_authority:Pubkey)->Instruction{
+        let mut accept_authority_accounts = Vec::<AccountMeta>::with_capacity(3);
+
+        accept_authority_accounts.push(AccountMeta::new_readonly(pending_authority, true));
+        accept_authority_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        accept_authority_accounts.push(AccountMeta::new(self.source_mint_exists, false));
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: accept_authority_accounts,
+            data: AcceptWrapperAuthority{}.data()
+        }
+    }
+
+    pub fn get_set_guardian_instruction(&self, authority:Pubkey, guardian:Pubkey)->Instruction{
+        let mut set_guardian_accounts = Vec::<AccountMeta>::with_capacity(3);
+
+        set_guardian_accounts.push(AccountMeta::new_readonly(authority, true));
+        set_guardian_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        set_guardian_accounts.push(AccountMeta::new(self.source_mint_exists, false));
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: set_guardian_accounts,
+            data: SetGuardian{ guardian }.data()
+        }
+    }
+
+    pub fn get_close_wrapper_instruction(&self)->Instruction{
+        let mut close_wrapper_accounts = Vec::<AccountMeta>::with_capacity(7);
+
+        close_wrapper_accounts.push(AccountMeta::new(self.payer.pubkey(), true));
+        close_wrapper_accounts.push(AccountMeta::new_readonly(self.vault_authority, false));
+        close_wrapper_accounts.push(AccountMeta::new(self.vault, false));
+        close_wrapper_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        close_wrapper_accounts.push(AccountMeta::new_readonly(self.wrapped_mint.pubkey(), false));
+        close_wrapper_accounts.push(AccountMeta::new(self.source_mint_exists, false));
+        close_wrapper_accounts.push(AccountMeta::new_readonly(spl_token::ID, false));
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: close_wrapper_accounts,
+            data: CloseWrapper{}.data()
+        }
+    }
+
+    pub fn get_set_successor_instruction(&self, successor:&TokenWrapperTest)->Instruction{
+        let mut set_successor_accounts = Vec::<AccountMeta>::with_capacity(6);
+
+        set_successor_accounts.push(AccountMeta::new(self.payer.pubkey(), true));
+        set_successor_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        set_successor_accounts.push(AccountMeta::new_readonly(self.source_mint_exists, false));
+        set_successor_accounts.push(AccountMeta::new(self.get_successor_link(), false));
+        set_successor_accounts.push(AccountMeta::new_readonly(successor.source_mint_exists, false));
+        set_successor_accounts.push(AccountMeta::new_readonly(system_program::ID, false));
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: set_successor_accounts,
+            data: SetSuccessor{}.data()
+        }
+    }
+
+    pub fn get_successor_link(&self)->Pubkey{
+        let (successor_link, _) =
+            Pubkey::find_program_address(
+                &[b"successor", self.source_mint_exists.as_ref()],
+                &token_wrapper::ID,
+            );
+        successor_link
+    }
+
+    pub fn get_convert_accounts(&self, to:&TokenWrapperTest, successor_link:Option<Pubkey>)->Vec<AccountMeta>{
+        let mut convert_accounts = Vec::<AccountMeta>::with_capacity(18);
+
+        convert_accounts.push(AccountMeta::new(self.payer.pubkey(), true));
+        convert_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        convert_accounts.push(AccountMeta::new(self.buyer_wrapped_mint_ata, false));
+        convert_accounts.push(AccountMeta::new_readonly(self.vault_authority, false));
+        convert_accounts.push(AccountMeta::new(self.vault, false));
+        convert_accounts.push(AccountMeta::new(self.wrapped_mint.pubkey(), false));
+        convert_accounts.push(AccountMeta::new_readonly(self.source_mint_exists, false));
+        convert_accounts.push(AccountMeta::new(to.buyer_wrapped_mint_ata, false));
+        convert_accounts.push(AccountMeta::new(to.vault, false));
+        convert_accounts.push(AccountMeta::new_readonly(to.mint_authority, false));
+        convert_accounts.push(AccountMeta::new(to.wrapped_mint.pubkey(), false));
+        convert_accounts.push(AccountMeta::new_readonly(to.source_mint_exists, false));
+        convert_accounts.push(AccountMeta::new_readonly(system_program::ID, false));
+        convert_accounts.push(AccountMeta::new_readonly(spl_token::ID, false));
+        convert_accounts.push(AccountMeta::new_readonly(self.wrapped_token_program, false));
+        convert_accounts.push(AccountMeta::new_readonly(to.wrapped_token_program, false));
+        convert_accounts.push(AccountMeta::new_readonly(mollusk_svm_programs_token::associated_token::ID, false));
+        // No successor link when converting
+        convert_accounts.push(AccountMeta::new_readonly(successor_link.unwrap_or(token_wrapper::ID), false));
+
+        convert_accounts
+    }
+
+    pub fn get_migrate_holder_instruction(&self, successor:&TokenWrapperTest)->Instruction{
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: self.get_convert_accounts(successor, Some(self.get_successor_link())),
+            data: MigrateHolder{ amount: self.source_amount }.data()
+        }
+    }
+
+    pub fn get_convert_instruction(&self, to:&TokenWrapperTest)->Instruction{
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: self.get_convert_accounts(to, None),
+            data: Convert{ amount: self.source_amount }.data()
+        }
+    }
+
+    pub fn get_swap_group(&self, swap:&SwapType)->Vec<AccountMeta>{
+        let authority = match swap {
+            SwapType::SwapToSource=>self.vault_authority,
+            SwapType::SwapToWrapped=>self.mint_authority
+        };
+
+        vec![
+            AccountMeta::new(self.buyer_mint_ata, false),
+            AccountMeta::new(self.buyer_wrapped_mint_ata, false),
+            AccountMeta::new(self.vault, false),
+            AccountMeta::new_readonly(authority, false),
+            AccountMeta::new_readonly(self.source_mint.pubkey(), false),
+            AccountMeta::new(self.wrapped_mint.pubkey(), false),
+            AccountMeta::new_readonly(self.source_mint_exists, false),
+            AccountMeta::new_readonly(self.wrapped_token_program, false),
+        ]
+    }
+
+    pub fn get_batch_swap_instruction(wrappers:&[&TokenWrapperTest], swap:SwapType)->Instruction{
+        let mut batch_swap_accounts = Vec::<AccountMeta>::with_capacity(2 + 8 * wrappers.len());
+
+        batch_swap_accounts.push(AccountMeta::new_readonly(wrappers[0].payer.pubkey(), true));
+        batch_swap_accounts.push(AccountMeta::new_readonly(spl_token::ID, false));
+
+        for wrapper in wrappers {
+            batch_swap_accounts.extend(wrapper.get_swap_group(&swap));
+        }
+
+        let data = match swap {
+            SwapType::SwapToSource=>{
+                BatchSwapToSource{
+                    amounts: wrappers.iter().map(|wrapper| wrapper.source_amount).collect()
+                }.data()
+            },
+            SwapType::SwapToWrapped=>{
+                BatchSwapToWrapped{
+                    amounts: wrappers.iter().map(|wrapper| wrapper.wrap_amount).collect()
+                }.data()
+            }
+        };
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: batch_swap_accounts,
+            data
+        }
+    }
+
+    pub fn get_allowlist_accounts(&self)->(Pubkey, Pubkey){
+        let (extra_account_meta_list, _) =
+            Pubkey::find_program_address(
+                &[b"extra-account-metas", self.wrapped_mint.pubkey().as_ref()],
+                &allowlist_hook::ID,
+            );
+        let (allowlist_config, _) =
+            Pubkey::find_program_address(
+                &[b"allowlist-config", self.wrapped_mint.pubkey().as_ref()],
+                &allowlist_hook::ID,
+            );
+        (extra_account_meta_list, allowlist_config)
+    }
+
+    pub fn get_allowlist_entry(&self, wallet:&Pubkey)->Pubkey{
+        let (allowlist_entry, _) =
+            Pubkey::find_program_address(
+                &[b"allowlist", self.wrapped_mint.pubkey().as_ref(), wallet.as_ref()],
+                &allowlist_hook::ID,
+            );
+        allowlist_entry
+    }
+
+    pub fn get_allowlist_instruction(&self, wallet:Pubkey, add:bool)->Instruction{
+        let (_, allowlist_config) = self.get_allowlist_accounts();
+
+        let mut allowlist_accounts = Vec::<AccountMeta>::with_capacity(5);
+
+        allowlist_accounts.push(AccountMeta::new(self.admin.pubkey(), true));
+        allowlist_accounts.push(AccountMeta::new_readonly(self.wrapped_mint.pubkey(), false));
+        allowlist_accounts.push(AccountMeta::new_readonly(allowlist_config, false));
+        allowlist_accounts.push(AccountMeta::new(self.get_allowlist_entry(&wallet), false));
+
+        let data = if add {
+            allowlist_accounts.push(AccountMeta::new_readonly(system_program::ID, false));
+            allowlist_hook::instruction::AddToAllowlist{ wallet }.data()
+        } else {
+            allowlist_hook::instruction::RemoveFromAllowlist{ _wallet: wallet }.data()
+        };
+
+        Instruction {
+            program_id: allowlist_hook::ID,
+            accounts: allowlist_accounts,
+            data
+        }
+    }
+
+    pub fn get_create_wrapped_ata_instruction(&self, wallet:Pubkey)->Instruction{
+        let wrapped_ata = get_associated_token_address_with_program_id(
+            &wallet, &self.wrapped_mint.pubkey(), &self.wrapped_token_program);
+
+        Instruction {
+            program_id: mollusk_svm_programs_token::associated_token::ID,
+            accounts: vec![
+                AccountMeta::new(self.payer.pubkey(), true),
+                AccountMeta::new(wrapped_ata, false),
+                AccountMeta::new_readonly(wallet, false),
+                AccountMeta::new_readonly(self.wrapped_mint.pubkey(), false),
+                AccountMeta::new_readonly(system_program::ID, false),
+                AccountMeta::new_readonly(self.wrapped_token_program, false),
+            ],
+            data: vec![0]
+        }
+    }
+
+    /// A Token-2022 transfer of wrapped tokens from the buyer, with the accounts of the allowlist hook.
+    pub fn get_hooked_transfer_instruction(&self, wallet:Pubkey, amount:u64)->Instruction{
+        let wrapped_ata = get_associated_token_address_with_program_id(
+            &wallet, &self.wrapped_mint.pubkey(), &self.wrapped_token_program);
+
+        let mut transfer_instruction = spl_token_2022::instruction::transfer_checked(
+            &self.wrapped_token_program,
+            &self.buyer_wrapped_mint_ata,
+            &self.wrapped_mint.pubkey(),
+            &wrapped_ata,
+            &self.payer.pubkey(),
+            &[],
+            amount,
+            9
+        ).expect("Could not build the transfer");
+
+        let (extra_account_meta_list, _) = self.get_allowlist_accounts();
+
+        transfer_instruction.accounts.push(AccountMeta::new_readonly(self.get_allowlist_entry(&self.payer.pubkey()), false));
+        transfer_instruction.accounts.push(AccountMeta::new_readonly(self.get_allowlist_entry(&wallet), false));
+        transfer_instruction.accounts.push(AccountMeta::new_readonly(allowlist_hook::ID, false));
+        transfer_instruction.accounts.push(AccountMeta::new_readonly(extra_account_meta_list, false));
+
+        transfer_instruction
+    }
+
+    pub fn store_vault_amount(&self, accounts: &mut TokenWrapperAccountStore, amount: u64){
+        self.store_vault(accounts, amount, spl_token::state::AccountState::Initialized);
+    }
+
+    pub fn store_vault(&self, accounts: &mut TokenWrapperAccountStore, amount: u64,
+                state: spl_token::state::AccountState){
+        let vault_data = TokenAccount{
+            mint: self.source_mint.pubkey(),
+            owner: self.vault_authority,
+            amount,
+            delegate: CNone,
+            state,
+            is_native: CNone,
+            delegated_amount: 0,
+            close_authority: CNone
+        };
+
+        accounts.store_account(
+            self.vault, 
+            create_account_for_token_account(vault_data)
+        );
+    }
+
+    pub fn get_unwrap_ticket(&self, ticket_id:u64)->Pubkey{
+        let (unwrap_ticket, _) =
+            Pubkey::find_program_address(
+                &[b"unwrap-ticket", self.source_mint_exists.as_ref(),
+                    self.payer.pubkey().as_ref(), &ticket_id.to_le_bytes()],
+                &token_wrapper::ID,
+            );
+        unwrap_ticket
+    }
+
+    pub fn get_request_unwrap_instruction(&self, ticket_id:u64)->Instruction{
+        let mut request_unwrap_accounts = Vec::<AccountMeta>::with_capacity(8);
+
+        request_unwrap_accounts.push(AccountMeta::new(self.payer.pubkey(), true));
+        request_unwrap_accounts.push(AccountMeta::new(self.buyer_wrapped_mint_ata, false));
+        request_unwrap_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        request_unwrap_accounts.push(AccountMeta::new(self.wrapped_mint.pubkey(), false));
+        request_unwrap_accounts.push(AccountMeta::new(self.source_mint_exists, false));
+        request_unwrap_accounts.push(AccountMeta::new(self.get_unwrap_ticket(ticket_id), false));
+        request_unwrap_accounts.push(AccountMeta::new_readonly(system_program::ID, false));
+        request_unwrap_accounts.push(AccountMeta::new_readonly(self.wrapped_token_program, false));
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: request_unwrap_accounts,
+            data: RequestUnwrap{ _ticket_id: ticket_id, amount: self.source_amount }.data()
+        }
+    }
+
+    pub fn get_claim_unwrap_instruction(&self, ticket_id:u64)->Instruction{
+        let mut claim_unwrap_accounts = Vec::<AccountMeta>::with_capacity(10);
+
+        claim_unwrap_accounts.push(AccountMeta::new(self.payer.pubkey(), true));
+        claim_unwrap_accounts.push(AccountMeta::new(self.buyer_mint_ata, false));
+        claim_unwrap_accounts.push(AccountMeta::new_readonly(self.vault_authority, false));
+        claim_unwrap_accounts.push(AccountMeta::new(self.vault, false));
+        claim_unwrap_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        claim_unwrap_accounts.push(AccountMeta::new(self.source_mint_exists, false));
+        claim_unwrap_accounts.push(AccountMeta::new(self.get_unwrap_ticket(ticket_id), false));
+        claim_unwrap_accounts.push(AccountMeta::new_readonly(system_program::ID, false));
+        claim_unwrap_accounts.push(AccountMeta::new_readonly(spl_token::ID, false));
+        claim_unwrap_accounts.push(AccountMeta::new_readonly(mollusk_svm_programs_token::associated_token::ID, false));
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: claim_unwrap_accounts,
+            data: ClaimUnwrap{ _ticket_id: ticket_id }.data()
+        }
+    }
+
+    pub fn get_vesting_schedule(&self, beneficiary:&Pubkey, schedule_id:u64)->Pubkey{
+        let (vesting_schedule, _) =
+            Pubkey::find_program_address(
+                &[b"vesting", self.source_mint_exists.as_ref(), beneficiary.as_ref(), &schedule_id.to_le_bytes()],
+                &token_wrapper::ID,
+            );
+        vesting_schedule
+    }
+
+    pub fn get_create_vesting_instruction(&self, funder:Pubkey, schedule_id:u64,
+                amount:u64, start:i64, cliff:i64, end:i64)->Instruction{
+        let mut create_vesting_accounts = Vec::<AccountMeta>::with_capacity(10);
+
+        // The payer is the beneficiary of every schedule
+        create_vesting_accounts.push(AccountMeta::new(funder, true));
+        create_vesting_accounts.push(AccountMeta::new(get_associated_token_address(&funder, &self.source_mint.pubkey()), false));
+        create_vesting_accounts.push(AccountMeta::new_readonly(self.payer.pubkey(), false));
+        create_vesting_accounts.push(AccountMeta::new_readonly(self.vault_authority, false));
+        create_vesting_accounts.push(AccountMeta::new(self.vault, false));
+        create_vesting_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        create_vesting_accounts.push(AccountMeta::new(self.source_mint_exists, false));
+        create_vesting_accounts.push(AccountMeta::new(self.get_vesting_schedule(&self.payer.pubkey(), schedule_id), false));
+        create_vesting_accounts.push(AccountMeta::new_readonly(system_program::ID, false));
+        create_vesting_accounts.push(AccountMeta::new_readonly(spl_token::ID, false));
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: create_vesting_accounts,
+            data: CreateVesting{ _schedule_id: schedule_id, amount, start, cliff, end }.data()
+        }
+    }
+
+    pub fn get_claim_vested_instruction(&self, schedule_id:u64)->Instruction{
+        let mut claim_vested_accounts = Vec::<AccountMeta>::with_capacity(10);
+
+        claim_vested_accounts.push(AccountMeta::new(self.payer.pubkey(), true));
+        claim_vested_accounts.push(AccountMeta::new(self.buyer_mint_ata, false));
+        claim_vested_accounts.push(AccountMeta::new_readonly(self.vault_authority, false));
+        claim_vested_accounts.push(AccountMeta::new(self.vault, false));
+        claim_vested_accounts.push(AccountMeta::new_readonly(self.source_mint.pubkey(), false));
+        claim_vested_accounts.push(AccountMeta::new(self.source_mint_exists, false));
+        claim_vested_accounts.push(AccountMeta::new(self.get_vesting_schedule(&self.payer.pubkey(), schedule_id), false));
+        claim_vested_accounts.push(AccountMeta::new_readonly(system_program::ID, false));
+        claim_vested_accounts.push(AccountMeta::new_readonly(spl_token::ID, false));
+        claim_vested_accounts.push(AccountMeta::new_readonly(mollusk_svm_programs_token::associated_token::ID, false));
+
+        Instruction {
+            program_id: token_wrapper::ID,
+            accounts: claim_vested_accounts,
+            data: ClaimVested{ _schedule_id: schedule_id }.data()
+        }
+    }
+
     
 }
 